@@ -0,0 +1,177 @@
+//! Derive macros for `ecs_small`, use them through the re-exports in that crate.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Makes a type something that can be registered with the world and added to entities.
+///
+/// `#[component(name = "door")]` sets the name the component is known by, it defaults to the type's name.
+/// `#[component(storage = "sparse")]` keeps it in a `SparseStorage` for components only a few entities will have,
+/// the default is `"dense"`.
+#[proc_macro_derive(Component, attributes(component))]
+pub fn derive_component(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match component_impl(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn component_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let mut name = ident.to_string();
+    let mut storage = quote!(::ecs_small::DenseStorage<Self>);
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("component")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else if meta.path.is_ident("storage") {
+                let value = meta.value()?.parse::<LitStr>()?;
+                storage = match value.value().as_str() {
+                    "dense" => quote!(::ecs_small::DenseStorage<Self>),
+                    "sparse" => quote!(::ecs_small::SparseStorage<Self>),
+                    other => {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            format!("unknown storage \"{}\", expected \"dense\" or \"sparse\"", other),
+                        ))
+                    }
+                };
+                Ok(())
+            } else {
+                Err(meta.error("expected `name` or `storage`"))
+            }
+        })?;
+    }
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::ecs_small::Component for #ident #type_generics #where_clause {
+            const NAME: &'static str = #name;
+            type Storage = #storage;
+        }
+    })
+}
+
+/// Lets a struct whose fields are all components be spawned as one entity with `World::spawn`.
+#[proc_macro_derive(Bundle)]
+pub fn derive_bundle(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match bundle_impl(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn bundle_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(syn::Error::new_spanned(ident, "bundles have to be structs")),
+    };
+    let adds = match fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|field| {
+                let field = field.ident.as_ref().unwrap();
+                quote!(world.add_component_to_entity(entity, self.#field);)
+            })
+            .collect::<Vec<_>>(),
+        Fields::Unnamed(fields) => (0..fields.unnamed.len())
+            .map(|index| {
+                let index = syn::Index::from(index);
+                quote!(world.add_component_to_entity(entity, self.#index);)
+            })
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::ecs_small::Bundle for #ident #type_generics #where_clause {
+            fn add_to_entity(self, world: &mut ::ecs_small::World, entity: usize) {
+                #(#adds)*
+            }
+        }
+    })
+}
+
+/// Lets a struct's fields be read and written by path at runtime.
+///
+/// Every field has to implement `Reflect` itself unless it's marked `#[reflect(skip)]`.
+#[proc_macro_derive(Reflect, attributes(reflect))]
+pub fn derive_reflect(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match reflect_impl(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn reflect_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(syn::Error::new_spanned(ident, "only structs can derive Reflect")),
+    };
+    let mut reflected = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let mut skip = false;
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("reflect")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `skip`"))
+                }
+            })?;
+        }
+        if skip {
+            continue;
+        }
+        // Tuple struct fields are reached by their index, the same as in Rust
+        let (member, name) = match &field.ident {
+            Some(field_ident) => (quote!(#field_ident), field_ident.to_string()),
+            None => {
+                let index = syn::Index::from(index);
+                (quote!(#index), index.index.to_string())
+            }
+        };
+        let ty = &field.ty;
+        reflected.push((member, name, quote!(#ty).to_string().replace(' ', "")));
+    }
+    let infos = reflected.iter().map(|(_, name, type_name)| {
+        quote!(::ecs_small::FieldInfo { name: #name, type_name: #type_name })
+    });
+    let names: Vec<&String> = reflected.iter().map(|(_, name, _)| name).collect();
+    let gets = reflected.iter().map(|(member, name, _)| quote!(#name => ::ecs_small::Reflect::get_path(&self.#member, rest),));
+    let sets = reflected.iter().map(|(member, name, _)| quote!(#name => ::ecs_small::Reflect::set_path(&mut self.#member, rest, value),));
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::ecs_small::Reflect for #ident #type_generics #where_clause {
+            fn fields(&self) -> ::std::vec::Vec<::ecs_small::FieldInfo> {
+                ::std::vec![#(#infos),*]
+            }
+
+            fn get_path(&self, path: &str) -> ::std::result::Result<::ecs_small::Value, ::ecs_small::ReflectError> {
+                let (field, rest) = ::ecs_small::split_path(path);
+                match field {
+                    "" => ::std::result::Result::Err(::ecs_small::ReflectError::NotAValue(::std::vec![#(#names),*])),
+                    #(#gets)*
+                    _ => ::std::result::Result::Err(::ecs_small::ReflectError::NoField(field.to_string())),
+                }
+            }
+
+            fn set_path(&mut self, path: &str, value: ::ecs_small::Value) -> ::std::result::Result<(), ::ecs_small::ReflectError> {
+                let (field, rest) = ::ecs_small::split_path(path);
+                match field {
+                    "" => ::std::result::Result::Err(::ecs_small::ReflectError::NotAValue(::std::vec![#(#names),*])),
+                    #(#sets)*
+                    _ => ::std::result::Result::Err(::ecs_small::ReflectError::NoField(field.to_string())),
+                }
+            }
+        }
+    })
+}
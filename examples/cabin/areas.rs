@@ -0,0 +1,476 @@
+use crate::conditions::Condition;
+use crate::doors::{LockableComponent, OpenableComponent};
+use crate::items::{InventoryComponent, NameComponent, spawn_item};
+use crate::map::{AreaDefinition, ExitDefinition, LocationComponent, MapComponent, MapParseError, MapValue, explore};
+use crate::npc::{BehaviorComponent, NpcBundle, describe_npcs_at};
+use crate::player::{Command, get_player};
+use ecs_small::{Component, World};
+use std::collections::HashMap;
+use std::fmt;
+
+// Areas are the separate levels of the world, each loaded from its own map file
+// Grid areas keep their tiles in a MapComponent on the area entity
+#[derive(Component)]
+#[component(name = "area")]
+pub struct AreaComponent {
+    pub name: String,
+}
+
+// Which area an entity is in, and which of that area's rooms if any
+// Entities that aren't in a room stand on the area's grid at their LocationComponent
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Component)]
+#[component(name = "place")]
+pub struct PlaceComponent {
+    pub area: usize,
+    pub room: Option<usize>,
+}
+
+// Rooms are entities of their own, connected to each other and to map tiles through their exits
+#[derive(Component)]
+#[component(name = "room")]
+pub struct RoomComponent {
+    pub name: String,
+    description: String,
+    area: usize,
+    // Warmth lost every second while in the room, rooms are out of the wind so it defaults to 0
+    pub chill: u32,
+}
+
+// Where an exit leads, either a room or a tile on an area's grid
+#[derive(Debug, PartialEq)]
+enum ExitDestination {
+    Room(usize),
+    Tile { area: usize, location: LocationComponent },
+}
+
+pub struct Exit {
+    pub keyword: String,
+    destination: ExitDestination,
+    // Checked for the player each time they try the exit, exits without one are always open
+    pub condition: Option<Condition>,
+    // What the player says when the condition doesn't hold
+    blocked: Option<String>,
+}
+
+impl Exit {
+    // "go stairs" is enough to take the "up the stairs" exit
+    fn matches(&self, input: &str) -> bool {
+        if self.keyword == input {
+            return true;
+        }
+        let words: Vec<&str> = self.keyword.split_whitespace().collect();
+        !input.is_empty() && input.split_whitespace().all(|word| words.contains(&word))
+    }
+}
+
+// Given to rooms and to entities sitting on a map tile, such as a doorway
+#[derive(Component)]
+#[component(name = "exits")]
+pub struct ExitsComponent {
+    pub exits: Vec<Exit>,
+}
+
+impl ExitsComponent {
+    fn find(&self, input: &str) -> Option<&Exit> {
+        self.exits
+            .iter()
+            .find(|exit| exit.keyword == input)
+            .or_else(|| self.exits.iter().find(|exit| exit.matches(input)))
+    }
+
+    fn list_keywords(&self) -> Vec<&str> {
+        self.exits.iter().map(|exit| exit.keyword.as_str()).collect()
+    }
+}
+
+// Returned when one of the area files fails to load, says which area the error is in
+#[derive(Debug, PartialEq)]
+pub struct AreaLoadError {
+    area: String,
+    error: MapParseError,
+}
+
+impl fmt::Display for AreaLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} area, {}", self.area, self.error)
+    }
+}
+
+// Creates an entity for every area, room and doorway, then links up the exits between them
+// Returns the area entities by name
+pub fn load_areas(world: &mut World, areas: &[(&str, &str)]) -> Result<HashMap<String, usize>, AreaLoadError> {
+    let mut area_entities: HashMap<String, usize> = HashMap::new();
+    let mut room_entities: HashMap<(usize, String), usize> = HashMap::new();
+    let mut pending_exits: Vec<(String, usize, Vec<ExitDefinition>)> = Vec::new();
+
+    for (name, contents) in areas {
+        if area_entities.contains_key(*name) {
+            return Err(AreaLoadError {
+                area: name.to_string(),
+                error: MapParseError { line: 1, column: 1, reason: format!("there is already an area called \"{}\"", name) },
+            });
+        }
+        let definition = AreaDefinition::parse(contents).map_err(|error| AreaLoadError {
+            area: name.to_string(),
+            error,
+        })?;
+        let area_entity = world.new_entity();
+        world.add_component_to_entity(area_entity, AreaComponent { name: name.to_string() });
+        // Items written on a tile become item entities lying on that tile
+        for (location, item) in definition.items {
+            let item_entity = spawn_item(world, item);
+            world.add_component_to_entity(item_entity, location);
+            world.add_component_to_entity(item_entity, PlaceComponent { area: area_entity, room: None });
+        }
+        world.add_component_to_entity(area_entity, definition.map);
+        // Containers and npcs standing on the grid have to be on one of its tiles
+        let is_on_map = |world: &World, location: &LocationComponent| {
+            world.borrow_component::<MapComponent>().unwrap()[area_entity].as_ref().is_some_and(|map| map.area.contains_key(location))
+        };
+        for room in definition.rooms {
+            let room_entity = world.new_entity();
+            room_entities.insert((area_entity, room.name.clone()), room_entity);
+            world.add_component_to_entity(room_entity, RoomComponent {
+                name: room.name,
+                description: room.description,
+                area: area_entity,
+                chill: room.chill,
+            });
+        }
+        for container in definition.containers {
+            let error_at = |column: usize, reason: String| AreaLoadError {
+                area: name.to_string(),
+                error: MapParseError { line: container.line, column, reason },
+            };
+            let (room, location) = match &container.place.value {
+                MapValue::Word(room) => match room_entities.get(&(area_entity, room.clone())) {
+                    Some(room_entity) => (Some(*room_entity), None),
+                    None => return Err(error_at(container.place.column, format!("there is no room called \"{}\"", room))),
+                },
+                MapValue::Location(location) if is_on_map(world, location) => (None, Some(*location)),
+                MapValue::Location(location) => return Err(error_at(container.place.column, format!("there is no tile at {}", location))),
+                _ => return Err(error_at(container.place.column, String::from("a container must be in a room or at a location"))),
+            };
+            let mut inventory = InventoryComponent::default();
+            for item in container.contents {
+                let item_entity = spawn_item(world, item);
+                inventory.insert(world, item_entity).map_err(|reason| error_at(container.place.column, reason))?;
+            }
+            let container_entity = world.new_entity();
+            world.add_component_to_entity(container_entity, NameComponent { name: container.name });
+            world.add_component_to_entity(container_entity, inventory);
+            world.add_component_to_entity(container_entity, OpenableComponent { is_open: container.is_open });
+            if let Some(key_item) = container.key_item {
+                world.add_component_to_entity(container_entity, LockableComponent { key_item, is_locked: true });
+            }
+            world.add_component_to_entity(container_entity, PlaceComponent { area: area_entity, room });
+            if let Some(location) = location {
+                world.add_component_to_entity(container_entity, location);
+            }
+        }
+        for npc in definition.npcs {
+            let location = match npc.location.value {
+                MapValue::Location(location) => location,
+                _ => unreachable!("npcs are checked to start on a tile when parsed"),
+            };
+            if !is_on_map(world, &location) {
+                return Err(AreaLoadError {
+                    area: name.to_string(),
+                    error: MapParseError { line: npc.line, column: npc.location.column, reason: format!("there is no tile at {}", location) },
+                });
+            }
+            let mut inventory = InventoryComponent::default();
+            for item in npc.carries {
+                let item_entity = spawn_item(world, item);
+                inventory.insert(world, item_entity).expect("Npcs can carry as much as they like");
+            }
+            world.spawn(NpcBundle {
+                name: NameComponent { name: npc.name },
+                inventory,
+                behavior: BehaviorComponent { behavior: npc.behavior, description: npc.description },
+                location,
+                place: PlaceComponent { area: area_entity, room: None },
+            });
+        }
+        area_entities.insert(name.to_string(), area_entity);
+        pending_exits.push((name.to_string(), area_entity, definition.exits));
+    }
+
+    let mut exits: HashMap<usize, Vec<Exit>> = HashMap::new();
+    let mut doorways: HashMap<(usize, LocationComponent), usize> = HashMap::new();
+    for (name, area_entity, definitions) in pending_exits {
+        for definition in definitions {
+            let error_at = |column: usize, reason: String| AreaLoadError {
+                area: name.clone(),
+                error: MapParseError {
+                    line: definition.line,
+                    column,
+                    reason,
+                },
+            };
+            let find_room = |area: usize, room: &str| {
+                room_entities
+                    .get(&(area, room.to_string()))
+                    .copied()
+                    .ok_or_else(|| format!("there is no room called \"{}\"", room))
+            };
+            let owner = match &definition.from.value {
+                MapValue::Word(room) => find_room(area_entity, room).map_err(|reason| error_at(definition.from.column, reason))?,
+                MapValue::Location(location) => *doorways.entry((area_entity, *location)).or_insert_with(|| {
+                    let doorway = world.new_entity();
+                    world.add_component_to_entity(doorway, *location);
+                    world.add_component_to_entity(doorway, PlaceComponent { area: area_entity, room: None });
+                    doorway
+                }),
+                _ => return Err(error_at(definition.from.column, String::from("an exit must start from a room or a location"))),
+            };
+            // Places in another area are written as <area>=<room or location>
+            let (destination_area, place) = match &definition.to.value {
+                MapValue::Property(other, place) => match area_entities.get(other) {
+                    Some(other_entity) => (*other_entity, place.as_ref()),
+                    None => return Err(error_at(definition.to.column, format!("there is no area called \"{}\"", other))),
+                },
+                place => (area_entity, place),
+            };
+            let destination = match place {
+                MapValue::Word(room) => {
+                    ExitDestination::Room(find_room(destination_area, room).map_err(|reason| error_at(definition.to.column, reason))?)
+                }
+                MapValue::Location(location) => ExitDestination::Tile {
+                    area: destination_area,
+                    location: *location,
+                },
+                _ => return Err(error_at(definition.to.column, String::from("an exit must lead to a room or a location"))),
+            };
+            exits.entry(owner).or_default().push(Exit {
+                keyword: definition.keyword,
+                destination,
+                condition: definition.condition,
+                blocked: definition.blocked,
+            });
+        }
+    }
+
+    for (owner, exits) in exits {
+        world.add_component_to_entity(owner, ExitsComponent { exits });
+    }
+    Ok(area_entities)
+}
+
+// The area and room the entity is in, None for entities that aren't placed in the world
+pub fn get_place(world: &World, entity: usize) -> Option<PlaceComponent> {
+    world
+        .borrow_component::<PlaceComponent>()
+        .and_then(|places| places[entity])
+}
+
+pub fn describe_room(world: &World, room_entity: usize) -> String {
+    let rooms = world.borrow_component::<RoomComponent>().unwrap();
+    let room = rooms[room_entity].as_ref().expect("Room entity is missing its room");
+    let mut description = room.description.clone();
+    if let Some(exits) = world.borrow_component::<ExitsComponent>() {
+        if let Some(exits) = exits[room_entity].as_ref() {
+            description.push_str(&format!("\nI could {{{{Go}}}} {{{{{}}}}}", exits.list_keywords().join(", ")));
+        }
+    }
+    description
+}
+
+// Takes the player through an exit, either from their current room or from whatever is on their map tile
+pub fn update_room_system(world: &World, command_vec: &[&str], game_output: &mut String) {
+    let mut iter = command_vec.iter();
+    if !matches!(iter.next().map(|command| Command::from_str(command)), Some(Ok(Command::Go))) {
+        return;
+    }
+    let player_entity = get_player(world);
+    let keyword = iter.map(|word| word.to_lowercase()).collect::<Vec<_>>().join(" ");
+
+    let exits_ref = match world.borrow_component::<ExitsComponent>() {
+        Some(exits) => exits,
+        None => {
+            game_output.push_str("There's nowhere to go from here");
+            return;
+        }
+    };
+    let places = world.borrow_component::<PlaceComponent>().unwrap();
+    let locations = world.borrow_component::<LocationComponent>().unwrap();
+    let player_place = places[player_entity].expect("Player is not in the world");
+    let player_location = locations[player_entity].expect("Player does not have a location");
+
+    let available: Vec<&ExitsComponent> = match player_place.room {
+        Some(room_entity) => exits_ref[room_entity].iter().collect(),
+        None => exits_ref
+            .iter()
+            .enumerate()
+            .filter(|(entity, _)| {
+                *entity != player_entity && places[*entity] == Some(player_place) && locations[*entity] == Some(player_location)
+            })
+            .filter_map(|(_, exits)| exits.as_ref())
+            .collect(),
+    };
+    if available.is_empty() {
+        game_output.push_str("There's nowhere to go from here");
+        return;
+    }
+    if keyword.is_empty() {
+        let keywords: Vec<&str> = available.iter().flat_map(|exits| exits.list_keywords()).collect();
+        game_output.push_str(&format!("Where should I go? I could {{{{Go}}}} {{{{{}}}}}", keywords.join(", ")));
+        return;
+    }
+    let exit = match available.iter().find_map(|exits| exits.find(&keyword)) {
+        Some(exit) => exit,
+        None => {
+            game_output.push_str(&format!("I don't see a way to go {} from here", keyword));
+            return;
+        }
+    };
+    // The condition can look at where the player is, so nothing is borrowed while it's checked
+    drop(places);
+    drop(locations);
+    let is_open = exit.condition.as_ref().is_none_or(|condition| condition.evaluate_bool(world, player_entity).unwrap_or(false));
+    if !is_open {
+        game_output.push_str(exit.blocked.as_deref().unwrap_or("I can't go that way right now"));
+        return;
+    }
+    let mut places = world.borrow_component_mut::<PlaceComponent>().unwrap();
+    let mut locations = world.borrow_component_mut::<LocationComponent>().unwrap();
+
+    match exit.destination {
+        ExitDestination::Room(room_entity) => {
+            let rooms = world.borrow_component::<RoomComponent>().unwrap();
+            let area = rooms[room_entity].as_ref().expect("Exit leads to a missing room").area;
+            places[player_entity] = Some(PlaceComponent { area, room: Some(room_entity) });
+            drop(rooms);
+            drop(exits_ref);
+            game_output.push_str(&describe_room(world, room_entity));
+        }
+        ExitDestination::Tile { area, location } => {
+            places[player_entity] = Some(PlaceComponent { area, room: None });
+            locations[player_entity] = Some(location);
+            let maps = world.borrow_component::<MapComponent>().unwrap();
+            if let Some(Ok(description)) = maps[area].as_ref().map(|map| map.check_area(&location)) {
+                game_output.push_str(description);
+            }
+            drop(places);
+            drop(locations);
+            explore(world, player_entity);
+            for npc in describe_npcs_at(world, PlaceComponent { area, room: None }, location) {
+                game_output.push('\n');
+                game_output.push_str(&npc);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameEntities;
+    use crate::map::DEFAULT_BLOCKED_MESSAGE;
+    use crate::tests::{run, setup_world};
+    use ecs_small::Value;
+
+    #[test]
+    fn exits_lead_into_other_areas() {
+        let (mut world, _) = setup_world();
+        let areas = load_areas(&mut world, &[
+            ("porch", "{x:2,y:2}|\"The porch\"\nexit|{x:2,y:2}|door|house=hall"),
+            ("house", "room|hall|\"The hall\"\nexit|hall|outside|porch={x:2,y:2}"),
+        ])
+        .unwrap();
+        let (porch, house) = (areas["porch"], areas["house"]);
+        let rooms = world.borrow_component::<RoomComponent>().unwrap();
+        let hall = rooms.iter().position(|room| room.as_ref().is_some_and(|room| room.area == house && room.name == "hall")).unwrap();
+        drop(rooms);
+        let doorway = world
+            .query::<ExitsComponent>()
+            .into_iter()
+            .find(|entity| get_place(&world, *entity) == Some(PlaceComponent { area: porch, room: None }))
+            .unwrap();
+
+        let exits = world.borrow_component::<ExitsComponent>().unwrap();
+        assert_eq!(exits[doorway].as_ref().unwrap().exits[0].destination, ExitDestination::Room(hall));
+        assert_eq!(exits[hall].as_ref().unwrap().exits[0].destination, ExitDestination::Tile {
+            area: porch,
+            location: LocationComponent { x: 2, y: 2 },
+        });
+    }
+
+    #[test]
+    fn areas_must_exist_and_have_their_own_names() {
+        let (mut world, _) = setup_world();
+        let mut error = |areas: &[(&str, &str)]| load_areas(&mut world, areas).err().unwrap().to_string();
+        assert_eq!(
+            error(&[("porch", "{x:0,y:0}|\"The porch\"\nexit|{x:0,y:0}|door|attic=hall")]),
+            "porch area, line 2, column 21: there is no area called \"attic\""
+        );
+        assert_eq!(
+            error(&[("house", "room|hall|\"The hall\""), ("porch", "{x:0,y:0}|\"The porch\"\nexit|{x:0,y:0}|door|house=cellar")]),
+            "porch area, line 2, column 21: there is no room called \"cellar\""
+        );
+        assert_eq!(
+            error(&[("porch", "{x:0,y:0}|\"The porch\""), ("porch", "{x:1,y:0}|\"More porch\"")]),
+            "porch area, line 1, column 1: there is already an area called \"porch\""
+        );
+        // Whatever stands on the grid has to stand on one of its tiles
+        assert_eq!(
+            error(&[("porch", "{x:0,y:0}|\"The porch\"\ncontainer|{x:0,y:1}|mailbox|closed")]),
+            "porch area, line 2, column 11: there is no tile at x: 0, y:1"
+        );
+        assert_eq!(
+            error(&[("porch", "{x:0,y:0}|\"The porch\"\nnpc|{x:3,y:0}|dog|\"A dog\"|idle")]),
+            "porch area, line 2, column 5: there is no tile at x: 3, y:0"
+        );
+    }
+
+    #[test]
+    fn exits_stay_blocked_until_their_condition_holds() {
+        let (world, player_entity) = setup_world();
+        let door_entity = world.borrow_resource::<GameEntities>().unwrap().door.unwrap();
+        world.set_path(player_entity, "location.x", Value::Int(2)).unwrap();
+        world.set_path(player_entity, "location.y", Value::Int(2)).unwrap();
+        let outside = get_place(&world, player_entity).unwrap();
+        let go = |keyword: &str| {
+            let mut output = String::new();
+            update_room_system(&world, &["go", keyword], &mut output);
+            output
+        };
+        assert_eq!(go("door"), "The doorknob is frozen solid, it won't turn");
+        assert_eq!(go("window"), "The window is shut tight, I can't open it from out here");
+        assert_eq!(get_place(&world, player_entity), Some(outside));
+
+        world.set_path(door_entity, "door.is_frozen", Value::Bool(false)).unwrap();
+        assert!(go("door").starts_with("I'm finally inside"));
+        assert_ne!(get_place(&world, player_entity), Some(outside));
+
+        assert_eq!(
+            AreaDefinition::parse("exit|hall|out|yard|when=\"not\"").err().unwrap().to_string(),
+            "line 1, column 29: the condition ended early"
+        );
+    }
+
+    #[test]
+    fn walls_only_block_their_own_direction() {
+        let (mut world, player_entity) = setup_world();
+        let outside = get_place(&world, player_entity).unwrap().area;
+        let map = AreaDefinition::parse(
+            "{x:0,y:0}|\"The gate\"|wall=right|blocked=\"The fence is in the way\"\n\
+             {x:1,y:0}|\"The other side of the fence\"\n\
+             {x:0,y:1}|\"The path\"",
+        )
+        .unwrap()
+        .map;
+        world.add_component_to_entity(outside, map);
+        let location = |world: &World| world.borrow_component::<LocationComponent>().unwrap()[player_entity].unwrap();
+
+        // There is a tile to the right, but the wall is in the way and the tile's own message is said
+        assert_eq!(run(&world, "move right"), "The fence is in the way");
+        assert_eq!(location(&world), LocationComponent { x: 0, y: 0 });
+        assert_eq!(run(&world, "move forward"), "The path");
+        assert_eq!(location(&world), LocationComponent { x: 0, y: 1 });
+
+        // Tiles without a blocked message fall back to the default one
+        assert_eq!(run(&world, "move right"), DEFAULT_BLOCKED_MESSAGE);
+        assert_eq!(location(&world), LocationComponent { x: 0, y: 1 });
+    }
+}
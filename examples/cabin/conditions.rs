@@ -0,0 +1,437 @@
+use crate::areas::{AreaComponent, RoomComponent, get_place};
+use crate::dialogue::Flags;
+use crate::doors::DoorComponent;
+use crate::items::{InventoryComponent, Item};
+use crate::player::PlayerComponent;
+use crate::survival::WarmthComponent;
+use ecs_small::World;
+use std::fmt;
+
+// A value that a condition can produce or compare against
+#[derive(Debug, PartialEq, Clone)]
+pub enum ConditionValue {
+    Bool(bool),
+    Number(i64),
+    Text(String),
+}
+
+impl fmt::Display for ConditionValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConditionValue::Bool(value) => write!(f, "{}", value),
+            ConditionValue::Number(value) => write!(f, "{}", value),
+            ConditionValue::Text(value) => write!(f, "\"{}\"", value),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum CompareOp {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+// An expression over the state of the world such as
+//     player.room == "loft" and not door.frozen
+// Paths like door.frozen and calls like has(rock) are looked up in world_value
+#[derive(Debug, PartialEq, Clone)]
+pub enum Condition {
+    Literal(ConditionValue),
+    Path(String),
+    Call(String, Vec<String>),
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Compare(Box<Condition>, CompareOp, Box<Condition>),
+}
+
+// column starts counting at 1 from the start of the condition text
+#[derive(Debug, PartialEq)]
+pub struct ConditionParseError {
+    pub column: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum ConditionToken {
+    Word(String),
+    Number(i64),
+    Text(String),
+    Op(CompareOp),
+    Open,
+    Close,
+}
+
+fn tokenize_condition(input: &str) -> Result<Vec<(ConditionToken, usize)>, ConditionParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < chars.len() {
+        let c = chars[pos];
+        let column = pos + 1;
+        if c.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+        let token = match c {
+            '(' => {
+                pos += 1;
+                ConditionToken::Open
+            }
+            ')' => {
+                pos += 1;
+                ConditionToken::Close
+            }
+            '=' | '!' | '<' | '>' => {
+                let followed_by_equal = chars.get(pos + 1) == Some(&'=');
+                pos += if followed_by_equal { 2 } else { 1 };
+                ConditionToken::Op(match (c, followed_by_equal) {
+                    ('=', true) => CompareOp::Equal,
+                    ('!', true) => CompareOp::NotEqual,
+                    ('<', false) => CompareOp::Less,
+                    ('<', true) => CompareOp::LessEqual,
+                    ('>', false) => CompareOp::Greater,
+                    ('>', true) => CompareOp::GreaterEqual,
+                    _ => {
+                        return Err(ConditionParseError {
+                            column,
+                            reason: format!("'{}' needs to be followed by '='", c),
+                        })
+                    }
+                })
+            }
+            '"' => {
+                let end = chars[pos + 1..].iter().position(|c| *c == '"').ok_or(ConditionParseError {
+                    column,
+                    reason: String::from("text is missing its closing quote"),
+                })?;
+                let text = chars[pos + 1..pos + 1 + end].iter().collect();
+                pos += end + 2;
+                ConditionToken::Text(text)
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let start = pos;
+                pos += 1;
+                while pos < chars.len() && chars[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+                let digits: String = chars[start..pos].iter().collect();
+                ConditionToken::Number(digits.parse().map_err(|_| ConditionParseError {
+                    column,
+                    reason: format!("\"{}\" is not a number", digits),
+                })?)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = pos;
+                while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_' || chars[pos] == '.') {
+                    pos += 1;
+                }
+                ConditionToken::Word(chars[start..pos].iter().collect())
+            }
+            _ => {
+                return Err(ConditionParseError {
+                    column,
+                    reason: format!("unexpected '{}'", c),
+                })
+            }
+        };
+        tokens.push((token, column));
+    }
+    Ok(tokens)
+}
+
+// Recursive descent over the tokens, from loosest to tightest binding
+//     or -> and -> not -> comparison -> value
+struct ConditionParser {
+    tokens: Vec<(ConditionToken, usize)>,
+    pos: usize,
+    end_column: usize,
+}
+
+impl ConditionParser {
+    fn peek(&self) -> Option<&ConditionToken> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens.get(self.pos).map(|(_, column)| *column).unwrap_or(self.end_column)
+    }
+
+    fn error(&self, reason: impl Into<String>) -> ConditionParseError {
+        ConditionParseError {
+            column: self.column(),
+            reason: reason.into(),
+        }
+    }
+
+    fn next_is_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(ConditionToken::Word(w)) if w == word)
+    }
+
+    fn parse_or(&mut self) -> Result<Condition, ConditionParseError> {
+        let mut condition = self.parse_and()?;
+        while self.next_is_word("or") {
+            self.pos += 1;
+            condition = Condition::Or(Box::new(condition), Box::new(self.parse_and()?));
+        }
+        Ok(condition)
+    }
+
+    fn parse_and(&mut self) -> Result<Condition, ConditionParseError> {
+        let mut condition = self.parse_not()?;
+        while self.next_is_word("and") {
+            self.pos += 1;
+            condition = Condition::And(Box::new(condition), Box::new(self.parse_not()?));
+        }
+        Ok(condition)
+    }
+
+    fn parse_not(&mut self) -> Result<Condition, ConditionParseError> {
+        if self.next_is_word("not") {
+            self.pos += 1;
+            return Ok(Condition::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Condition, ConditionParseError> {
+        let left = self.parse_value()?;
+        if let Some(ConditionToken::Op(op)) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            let right = self.parse_value()?;
+            return Ok(Condition::Compare(Box::new(left), op, Box::new(right)));
+        }
+        Ok(left)
+    }
+
+    fn parse_value(&mut self) -> Result<Condition, ConditionParseError> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return Err(self.error("the condition ended early")),
+        };
+        self.pos += 1;
+        match token {
+            ConditionToken::Open => {
+                let condition = self.parse_or()?;
+                if self.peek() != Some(&ConditionToken::Close) {
+                    return Err(self.error("expected ')'"));
+                }
+                self.pos += 1;
+                Ok(condition)
+            }
+            ConditionToken::Number(number) => Ok(Condition::Literal(ConditionValue::Number(number))),
+            ConditionToken::Text(text) => Ok(Condition::Literal(ConditionValue::Text(text))),
+            ConditionToken::Word(word) if word == "true" => Ok(Condition::Literal(ConditionValue::Bool(true))),
+            ConditionToken::Word(word) if word == "false" => Ok(Condition::Literal(ConditionValue::Bool(false))),
+            ConditionToken::Word(word) if ["and", "or", "not"].contains(&word.as_str()) => {
+                self.pos -= 1;
+                Err(self.error(format!("expected a value before \"{}\"", word)))
+            }
+            ConditionToken::Word(word) if self.peek() == Some(&ConditionToken::Open) => {
+                self.pos += 1;
+                let mut args = Vec::new();
+                while let Some(ConditionToken::Word(arg)) = self.peek() {
+                    args.push(arg.clone());
+                    self.pos += 1;
+                }
+                if self.peek() != Some(&ConditionToken::Close) {
+                    return Err(self.error(format!("expected ')' to close {}(", word)));
+                }
+                self.pos += 1;
+                Ok(Condition::Call(word, args))
+            }
+            ConditionToken::Word(word) => Ok(Condition::Path(word)),
+            _ => {
+                self.pos -= 1;
+                Err(self.error("expected a value"))
+            }
+        }
+    }
+}
+
+impl Condition {
+    pub fn parse(input: &str) -> Result<Condition, ConditionParseError> {
+        let mut parser = ConditionParser {
+            tokens: tokenize_condition(input)?,
+            pos: 0,
+            end_column: input.chars().count() + 1,
+        };
+        let condition = parser.parse_or()?;
+        if parser.peek().is_some() {
+            return Err(parser.error("unexpected text after the condition"));
+        }
+        Ok(condition)
+    }
+
+    // Both sides of and/or are always evaluated so a bad path can't hide behind a short circuit
+    fn evaluate(&self, world: &World, entity: usize) -> Result<ConditionValue, String> {
+        match self {
+            Condition::Literal(value) => Ok(value.clone()),
+            Condition::Path(path) => world_value(world, entity, path),
+            Condition::Call(name, args) => world_call(world, entity, name, args),
+            Condition::Not(inner) => Ok(ConditionValue::Bool(!inner.evaluate_bool(world, entity)?)),
+            Condition::And(left, right) => {
+                let left = left.evaluate_bool(world, entity)?;
+                let right = right.evaluate_bool(world, entity)?;
+                Ok(ConditionValue::Bool(left && right))
+            }
+            Condition::Or(left, right) => {
+                let left = left.evaluate_bool(world, entity)?;
+                let right = right.evaluate_bool(world, entity)?;
+                Ok(ConditionValue::Bool(left || right))
+            }
+            Condition::Compare(left, op, right) => {
+                let left = left.evaluate(world, entity)?;
+                let right = right.evaluate(world, entity)?;
+                let ordering = match (&left, &right) {
+                    (ConditionValue::Number(l), ConditionValue::Number(r)) => l.cmp(r),
+                    (ConditionValue::Text(l), ConditionValue::Text(r)) => l.cmp(r),
+                    (ConditionValue::Bool(l), ConditionValue::Bool(r)) if matches!(op, CompareOp::Equal | CompareOp::NotEqual) => l.cmp(r),
+                    _ => return Err(format!("can't compare {} with {}", left, right)),
+                };
+                Ok(ConditionValue::Bool(match op {
+                    CompareOp::Equal => ordering.is_eq(),
+                    CompareOp::NotEqual => ordering.is_ne(),
+                    CompareOp::Less => ordering.is_lt(),
+                    CompareOp::LessEqual => ordering.is_le(),
+                    CompareOp::Greater => ordering.is_gt(),
+                    CompareOp::GreaterEqual => ordering.is_ge(),
+                }))
+            }
+        }
+    }
+
+    pub fn evaluate_bool(&self, world: &World, entity: usize) -> Result<bool, String> {
+        match self.evaluate(world, entity)? {
+            ConditionValue::Bool(value) => Ok(value),
+            other => Err(format!("expected true or false but found {}", other)),
+        }
+    }
+}
+
+// The world state that conditions can read, relative to the entity the condition is checked for
+fn world_value(world: &World, entity: usize, path: &str) -> Result<ConditionValue, String> {
+    let place = get_place(world, entity);
+    match path {
+        "player.alive" => {
+            let players = world.borrow_component::<PlayerComponent>().ok_or("there are no players")?;
+            let player = players[entity].as_ref().ok_or("the entity is not a player")?;
+            Ok(ConditionValue::Bool(player.is_alive))
+        }
+        "player.warmth" => {
+            let warmths = world.borrow_component::<WarmthComponent>().ok_or("nothing has warmth")?;
+            let warmth = warmths[entity].as_ref().ok_or("the entity does not have warmth")?;
+            Ok(ConditionValue::Number(warmth.warmth as i64))
+        }
+        "player.area" => {
+            let area = place.ok_or("the entity is not in the world")?.area;
+            let areas = world.borrow_component::<AreaComponent>().unwrap();
+            Ok(ConditionValue::Text(areas[area].as_ref().map(|area| area.name.clone()).unwrap_or_default()))
+        }
+        // Empty while out on the grid
+        "player.room" => match place.and_then(|place| place.room) {
+            Some(room) => {
+                let rooms = world.borrow_component::<RoomComponent>().unwrap();
+                Ok(ConditionValue::Text(rooms[room].as_ref().map(|room| room.name.clone()).unwrap_or_default()))
+            }
+            None => Ok(ConditionValue::Text(String::new())),
+        },
+        "door.frozen" | "door.window_intact" | "door.gasolined" => {
+            let doors = world.borrow_component::<DoorComponent>().ok_or("there are no doors")?;
+            let (_, door) = doors.iter().next().ok_or("there are no doors")?;
+            Ok(ConditionValue::Bool(match path {
+                "door.frozen" => door.is_frozen,
+                "door.window_intact" => door.is_window_intact,
+                _ => door.is_gasolined,
+            }))
+        }
+        // Flags that were never set are false
+        _ if path.starts_with("flag.") => {
+            let flag = &path["flag.".len()..];
+            Ok(ConditionValue::Bool(world.borrow_resource::<Flags>().is_some_and(|flags| flags.set.contains(flag))))
+        }
+        _ => Err(format!("unknown value \"{}\"", path)),
+    }
+}
+
+fn world_call(world: &World, entity: usize, name: &str, args: &[String]) -> Result<ConditionValue, String> {
+    match (name, args) {
+        ("has", [item]) => {
+            let item = Item::from_str(item).map_err(|_| format!("\"{}\" is not an item", item))?;
+            let inventories = world.borrow_component::<InventoryComponent>().ok_or("nothing holds any items")?;
+            let inventory = inventories[entity].as_ref().ok_or("the entity can't hold items")?;
+            Ok(ConditionValue::Bool(inventory.quantity(item) > 0))
+        }
+        ("has", _) => Err(String::from("has() takes a single item")),
+        _ => Err(format!("unknown check \"{}\"", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::setup_world;
+
+    fn path(name: &str) -> Box<Condition> {
+        Box::new(Condition::Path(String::from(name)))
+    }
+
+    fn check(world: &World, entity: usize, condition: &str) -> Result<bool, String> {
+        Condition::parse(condition).unwrap().evaluate_bool(world, entity)
+    }
+
+    #[test]
+    fn conditions_bind_not_then_and_then_or() {
+        assert_eq!(Condition::parse("a or b and c"), Ok(Condition::Or(path("a"), Box::new(Condition::And(path("b"), path("c"))))));
+        assert_eq!(Condition::parse("not a and b"), Ok(Condition::And(Box::new(Condition::Not(path("a"))), path("b"))));
+        assert_eq!(Condition::parse("(a or b) and c"), Ok(Condition::And(Box::new(Condition::Or(path("a"), path("b"))), path("c"))));
+        // A comparison is tighter still, so not applies to the whole of it
+        assert_eq!(
+            Condition::parse("not a == 1"),
+            Ok(Condition::Not(Box::new(Condition::Compare(path("a"), CompareOp::Equal, Box::new(Condition::Literal(ConditionValue::Number(1)))))))
+        );
+    }
+
+    #[test]
+    fn condition_errors_point_at_the_column() {
+        let error = |condition: &str| Condition::parse(condition).err().map(|err| (err.column, err.reason));
+        assert_eq!(error("(a or b"), Some((8, String::from("expected ')'"))));
+        assert_eq!(error("a or b)"), Some((7, String::from("unexpected text after the condition"))));
+        assert_eq!(error("has(rock"), Some((9, String::from("expected ')' to close has("))));
+        assert_eq!(error("a and or b"), Some((7, String::from("expected a value before \"or\""))));
+        assert_eq!(error("a = b"), Some((3, String::from("'=' needs to be followed by '='"))));
+        assert_eq!(error("a == \"loft"), Some((6, String::from("text is missing its closing quote"))));
+    }
+
+    #[test]
+    fn conditions_compare_numbers_text_and_world_values() {
+        let (world, player_entity) = setup_world();
+        let holds = |condition: &str| check(&world, player_entity, condition);
+        assert_eq!(holds("1 == 1 and 1 != 2"), Ok(true));
+        assert_eq!(holds("1 < 2 and 2 <= 2 and 3 > 2 and 3 >= 3"), Ok(true));
+        assert_eq!(holds("2 < 1 or 3 <= 2 or 2 > 3 or 2 >= 3"), Ok(false));
+        assert_eq!(holds("\"cabin\" < \"loft\" and \"a\" != \"b\""), Ok(true));
+        assert_eq!(holds("player.alive and player.area == \"outside\" and player.room == \"\""), Ok(true));
+        assert_eq!(holds("has(lighter) and not has(rock) and door.frozen"), Ok(true));
+        assert_eq!(holds("true == 1"), Err(String::from("can't compare true with 1")));
+        assert_eq!(holds("true < false"), Err(String::from("can't compare true with false")));
+        assert_eq!(holds("1 and true"), Err(String::from("expected true or false but found 1")));
+    }
+
+    #[test]
+    fn unknown_values_are_errors() {
+        let (world, player_entity) = setup_world();
+        let holds = |condition: &str| check(&world, player_entity, condition);
+        assert_eq!(holds("player.mood == \"calm\""), Err(String::from("unknown value \"player.mood\"")));
+        // Both sides are evaluated so the mistake still shows up behind a true or
+        assert_eq!(holds("true or player.mood"), Err(String::from("unknown value \"player.mood\"")));
+        assert_eq!(holds("has(dragon)"), Err(String::from("\"dragon\" is not an item")));
+        assert_eq!(holds("sing(loudly)"), Err(String::from("unknown check \"sing\"")));
+        assert_eq!(holds("flag.never_set"), Ok(false));
+    }
+}
@@ -0,0 +1,177 @@
+use crate::GameState;
+use crate::areas::{AreaComponent, PlaceComponent, get_place};
+use crate::items::{InventoryComponent, Item, NameComponent, spawn_item};
+use crate::map::{DEFAULT_TILE_CHILL, LocationComponent, MapComponent, explore};
+use crate::npc::{Behavior, BehaviorComponent, NpcBundle};
+use crate::player::get_player;
+use crate::survival::{GameClock, WarmthComponent, get_chill};
+use ecs_small::{App, Plugin, World};
+
+// Developer commands for getting straight to the part of the game being tested, see DevConsole
+pub struct DevToolsPlugin;
+
+impl Plugin<GameState> for DevToolsPlugin {
+    fn build(&self, app: &mut App<GameState>) {
+        app.add_dev_command("tp", "tp <x> <y> [area]", teleport_command)
+            .add_dev_command("give", "give <item>", give_command)
+            .add_dev_command("time", "time set <seconds>", time_command)
+            .add_dev_command("spawn", "spawn npc [name] [behavior]", spawn_command)
+            .enable_dev_console();
+    }
+}
+
+pub fn teleport_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let location = match args {
+        [x, y, ..] => match (x.parse(), y.parse()) {
+            (Ok(x), Ok(y)) => LocationComponent { x, y },
+            _ => return Err(String::from("The coordinates have to be whole numbers")),
+        },
+        _ => return Err(String::from("Teleport where?")),
+    };
+    let player_entity = get_player(world);
+    let area = match args.get(2) {
+        Some(name) => world
+            .query::<AreaComponent>()
+            .into_iter()
+            .find(|area| world.borrow_component::<AreaComponent>().unwrap()[*area].as_ref().is_some_and(|area| area.name == *name))
+            .ok_or(format!("There's no area called {}", name))?,
+        None => get_place(world, player_entity).ok_or("The player isn't anywhere")?.area,
+    };
+    let area_name = world.borrow_component::<AreaComponent>().unwrap()[area].as_ref().unwrap().name.clone();
+    let has_tile = world.borrow_component::<MapComponent>().unwrap()[area]
+        .as_ref()
+        .is_some_and(|map| map.area.contains_key(&location));
+    if !has_tile {
+        return Err(format!("There's no tile at {} in the {} area", location, area_name));
+    }
+    world.add_component_to_entity(player_entity, location);
+    world.add_component_to_entity(player_entity, PlaceComponent { area, room: None });
+    explore(world, player_entity);
+    Ok(format!("Teleported to {} in the {} area", location, area_name))
+}
+
+pub fn give_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let kind = Item::from_str(args.first().ok_or("Give what?")?).map_err(|item| format!("There's no item called {}", item))?;
+    let item_entity = spawn_item(world, kind);
+    let player_entity = get_player(world);
+    let mut inventories = world.borrow_component_mut::<InventoryComponent>().unwrap();
+    inventories[player_entity].as_mut().unwrap().insert(world, item_entity)?;
+    Ok(format!("Added a {} to the player's pockets", kind.to_string().to_lowercase()))
+}
+
+// Sets how long the player has before they freeze where they're standing
+// Out of the wind the time is counted at the default chill, it only starts running once they're back out in it
+fn time_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let seconds: u32 = match args {
+        ["set", seconds] => seconds.parse().map_err(|_| "The time has to be a whole number of seconds")?,
+        _ => return Err(String::from("Set the time to what?")),
+    };
+    let player_entity = get_player(world);
+    let chill = get_chill(world, player_entity);
+    let mut warmths = world.borrow_component_mut::<WarmthComponent>().unwrap();
+    let warmth = warmths[player_entity].as_mut().ok_or("The player doesn't feel the cold")?;
+    let per_second = if chill == 0 { DEFAULT_TILE_CHILL } else { chill } as f64;
+    if seconds as f64 * per_second > warmth.max_warmth {
+        return Err(format!("The player can last at most {} seconds here", (warmth.max_warmth / per_second).floor()));
+    }
+    warmth.warmth = seconds as f64 * per_second;
+    warmth.chill = chill;
+    drop(warmths);
+    // Only the time from now on counts against it
+    world.borrow_resource_mut::<GameClock>().unwrap().resume();
+    Ok(format!("The player will freeze in {} seconds", seconds))
+}
+
+fn spawn_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let (name, behavior) = match args {
+        ["npc"] => ("stranger", "idle"),
+        ["npc", name] => (*name, "idle"),
+        ["npc", name, behavior] => (*name, *behavior),
+        _ => return Err(String::from("Spawn what?")),
+    };
+    let behavior = Behavior::from_str(behavior).map_err(|_| format!("There's no behavior called {}", behavior))?;
+    let player_entity = get_player(world);
+    let place = get_place(world, player_entity).ok_or("The player isn't anywhere")?;
+    if place.room.is_some() {
+        return Err(String::from("NPCs can only be spawned out on the map"));
+    }
+    let location = world.borrow_component::<LocationComponent>().unwrap()[player_entity].unwrap();
+    let npc_entity = world.spawn(NpcBundle {
+        name: NameComponent { name: name.to_string() },
+        inventory: InventoryComponent::default(),
+        behavior: BehaviorComponent { behavior, description: format!("{} is standing here", name) },
+        location,
+        place,
+    });
+    Ok(format!("Spawned {} as entity {}", name, npc_entity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endings::{Endings, game_ending_system};
+    use crate::player::PlayerComponent;
+    use crate::tests::{held_kinds, npc_named, pass_time, play, setup_world, warmth_of};
+    use ecs_small::Value;
+
+    #[test]
+    fn setting_the_time_left_brings_the_freezing_ending_closer() {
+        let (mut world, player_entity) = setup_world();
+        // The edge of the woods takes two points of warmth a second
+        world.set_path(player_entity, "location.x", Value::Int(1)).unwrap();
+        assert_eq!(time_command(&mut world, &["set", "10"]), Ok(String::from("The player will freeze in 10 seconds")));
+        assert_eq!(warmth_of(&world, player_entity), 20.0);
+        assert_eq!(time_command(&mut world, &["set", "60"]), Err(String::from("The player can last at most 50 seconds here")));
+        pass_time(&world, 9);
+        assert!(world.borrow_component::<PlayerComponent>().unwrap()[player_entity].as_ref().unwrap().is_alive);
+
+        time_command(&mut world, &["set", "10"]).unwrap();
+        pass_time(&world, 10);
+        let outcome = game_ending_system(&world, player_entity, &world.borrow_resource::<Endings>().unwrap().endings).unwrap();
+        assert_eq!(outcome.name, "frozen");
+    }
+
+    fn dev_commands(player_entity: usize) -> Vec<String> {
+        vec![
+            String::from("/tp 2 1"),
+            String::from("/give rock"),
+            String::from("/time set 50"),
+            String::from("/spawn npc bob wander"),
+            String::from("/list entities"),
+            format!("/inspect {}", player_entity),
+        ]
+    }
+
+    #[test]
+    fn dev_commands_run_with_the_console_on() {
+        let (_, player_entity) = setup_world();
+        let (world, shown) = play(true, &dev_commands(player_entity));
+        // The menu and the intro come before the commands
+        let output = &shown[2..];
+        assert_eq!(output[0], "> Teleported to x: 2, y:1 in the outside area");
+        assert_eq!(output[1], "> Added a rock to the player's pockets");
+        assert_eq!(output[2], "> The player will freeze in 50 seconds");
+        let bob = npc_named(&world, "bob");
+        assert_eq!(output[3], format!("> Spawned bob as entity {}", bob));
+        assert!(output[4].ends_with(&format!("\n> {}: name, inventory, location, place, behavior", bob)));
+        assert!(output[5].starts_with(&format!("> Entity {}", player_entity)));
+        assert!(output[5].contains(">   location\n>     x: i32 = 2\n>     y: i32 = 1"));
+
+        assert_eq!(world.borrow_component::<LocationComponent>().unwrap()[player_entity], Some(LocationComponent { x: 2, y: 1 }));
+        assert!(held_kinds(&world, player_entity).contains(&Item::Rock));
+        assert_eq!(world.borrow_component::<WarmthComponent>().unwrap()[player_entity].as_ref().unwrap().remaining_time(), Some(50));
+    }
+
+    #[test]
+    fn dev_commands_are_just_bad_input_with_the_console_off() {
+        let (_, player_entity) = setup_world();
+        let (world, shown) = play(false, &dev_commands(player_entity));
+        for frame in &shown[2..] {
+            assert!(frame.starts_with("> Error bad input"), "{}", frame);
+        }
+        assert_eq!(world.borrow_component::<LocationComponent>().unwrap()[player_entity], Some(LocationComponent { x: 0, y: 0 }));
+        assert_eq!(held_kinds(&world, player_entity), vec![Item::Lighter, Item::Watch]);
+        let names = world.borrow_component::<NameComponent>().unwrap();
+        assert!(!names.iter().flatten().any(|name| name.name == "bob"));
+    }
+}
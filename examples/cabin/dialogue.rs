@@ -0,0 +1,360 @@
+use crate::{GameState, set_state};
+use crate::conditions::Condition;
+use crate::items::{InventoryComponent, Item, NameComponent, drop_item, find_item, give_item};
+use crate::map::{MapField, MapLineScanner, MapParseError, MapValue};
+use crate::npc::find_named_entity;
+use crate::player::{Command, get_player};
+use crate::survival::{GameEvent, Scheduler};
+use ecs_small::{App, Plugin, World};
+use std::collections::{HashMap, HashSet};
+
+// Things that have happened in the game which conditions can check as flag.<name>, set by dialogue
+#[derive(Default)]
+pub struct Flags {
+    pub set: HashSet<String>,
+}
+
+enum DialogueEffect {
+    // The NPC hands the player one of the items they carry
+    Give(Item),
+    SetFlag(String),
+    // Schedules the event to happen this many seconds after the choice is made
+    StartEvent { after: u64, event: GameEvent },
+}
+
+struct DialogueChoice {
+    line: usize,
+    text: String,
+    // None ends the conversation
+    next: Option<String>,
+    // Choices whose condition doesn't hold aren't offered
+    condition: Option<Condition>,
+    effects: Vec<DialogueEffect>,
+}
+
+struct DialogueNode {
+    text: String,
+    choices: Vec<DialogueChoice>,
+}
+
+pub struct Dialogue {
+    start: String,
+    nodes: HashMap<String, DialogueNode>,
+}
+
+// Every NPC's dialogue by the NPC's name, loaded from dialogue.txt
+#[derive(Default)]
+struct Dialogues {
+    by_npc: HashMap<String, Dialogue>,
+}
+
+// Who the player is talking to and where they are in the conversation
+// While this resource holds a conversation every input is taken as picking one of the choices
+#[derive(Default)]
+struct Conversation {
+    active: Option<(usize, String)>,
+}
+
+// Dialogue files have two kinds of lines
+//     node|<npc>|<node>|"<what the npc says>"
+//     choice|<npc>|<node>|"<what I say>"|to=<node>|when="<condition>"|give=<item>|set=<flag>|say="<text>"|in=<seconds>
+// The first node of each npc is where talking to them starts, to=end or leaving out to= finishes the conversation
+// say= starts an event in= seconds after the choice is made, straight away if in= is left out
+fn load_dialogues(contents: &str) -> Result<Dialogues, MapParseError> {
+    let mut dialogues = Dialogues::default();
+    for (index, line) in contents.lines().enumerate() {
+        let mut scanner = MapLineScanner::new(index + 1, line);
+        let fields = scanner.parse_fields()?;
+        let kind = match fields.first() {
+            None => continue,
+            Some(MapField { value: MapValue::Word(kind), .. }) if kind == "node" || kind == "choice" => kind.clone(),
+            Some(field) => return Err(scanner.error_at(field.column, "expected node or choice")),
+        };
+        let (npc, node, text) = match &fields[1..] {
+            [
+                MapField { value: MapValue::Word(npc) | MapValue::Text(npc), .. },
+                MapField { value: MapValue::Word(node), .. },
+                MapField { value: MapValue::Text(text), .. },
+                ..
+            ] => (npc.to_lowercase(), node.clone(), text.clone()),
+            _ => return Err(scanner.error(format!("a {} is written as {}|<npc>|<node>|\"<text>\"", kind, kind))),
+        };
+        if kind == "node" {
+            if let Some(field) = fields.get(4) {
+                return Err(scanner.error_at(field.column, "a node only has the text the npc says"));
+            }
+            let dialogue = dialogues.by_npc.entry(npc).or_insert_with(|| Dialogue { start: node.clone(), nodes: HashMap::new() });
+            if dialogue.nodes.contains_key(&node) {
+                return Err(scanner.error_at(fields[2].column, format!("node \"{}\" is already defined", node)));
+            }
+            dialogue.nodes.insert(node, DialogueNode { text, choices: Vec::new() });
+            continue;
+        }
+
+        let mut choice = DialogueChoice {
+            line: scanner.line,
+            text,
+            next: None,
+            condition: None,
+            effects: Vec::new(),
+        };
+        let mut say = None;
+        let mut after = 0;
+        for field in &fields[4..] {
+            let (property, value) = match &field.value {
+                MapValue::Property(property, value) => (property.as_str(), value.as_ref()),
+                _ => return Err(scanner.error_at(field.column, "expected a property like to=<node>")),
+            };
+            match (property, value) {
+                ("to", MapValue::Word(next)) => choice.next = Some(next.clone()).filter(|next| next != "end"),
+                ("when", MapValue::Text(when)) => {
+                    let offset = field.column + property.len() + 1;
+                    choice.condition = Some(Condition::parse(when).map_err(|err| scanner.error_at(offset + err.column, err.reason))?);
+                }
+                ("give", MapValue::Word(item)) => match Item::from_str(item) {
+                    Ok(item) => choice.effects.push(DialogueEffect::Give(item)),
+                    Err(_) => return Err(scanner.error_at(field.column, format!("\"{}\" is not an item", item))),
+                },
+                ("set", MapValue::Word(flag)) => choice.effects.push(DialogueEffect::SetFlag(flag.clone())),
+                ("say", MapValue::Text(text)) => say = Some(text.clone()),
+                ("in", MapValue::Word(word)) => {
+                    after = word.parse().map_err(|_| scanner.error_at(field.column, "in must be a whole number of seconds"))?;
+                }
+                _ => return Err(scanner.error_at(field.column, format!("unexpected choice property \"{}\"", property))),
+            }
+        }
+        if let Some(text) = say {
+            choice.effects.push(DialogueEffect::StartEvent { after, event: GameEvent::Say(text) });
+        }
+        let node = dialogues
+            .by_npc
+            .get_mut(&npc)
+            .and_then(|dialogue| dialogue.nodes.get_mut(&node))
+            .ok_or_else(|| scanner.error_at(fields[2].column, format!("node \"{}\" has to be written before its choices", node)))?;
+        node.choices.push(choice);
+    }
+    Ok(dialogues)
+}
+
+// Checks every choice leads somewhere and that its condition can be evaluated against the world
+fn validate_dialogues(world: &World, player_entity: usize, dialogues: &Dialogues) -> Result<(), String> {
+    for (npc, dialogue) in &dialogues.by_npc {
+        for choice in dialogue.nodes.values().flat_map(|node| &node.choices) {
+            if let Some(next) = &choice.next {
+                if !dialogue.nodes.contains_key(next) {
+                    return Err(format!("line {}, {} has no node called \"{}\"", choice.line, npc, next));
+                }
+            }
+            if let Some(condition) = &choice.condition {
+                condition
+                    .evaluate_bool(world, player_entity)
+                    .map_err(|err| format!("line {}, {}", choice.line, err))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// What the NPC says at the node followed by the numbered choices that are currently available
+// Returns the choices by the number the player types in
+fn available_choices<'a>(world: &World, player_entity: usize, node: &'a DialogueNode) -> Vec<&'a DialogueChoice> {
+    node.choices
+        .iter()
+        .filter(|choice| {
+            choice
+                .condition
+                .as_ref()
+                .is_none_or(|condition| condition.evaluate_bool(world, player_entity).unwrap_or(false))
+        })
+        .collect()
+}
+
+fn describe_node(world: &World, player_entity: usize, speaker: &str, node: &DialogueNode) -> String {
+    let mut description = format!("{}: \"{}\"", speaker, node.text);
+    for (number, choice) in available_choices(world, player_entity, node).iter().enumerate() {
+        description.push_str(&format!("\n{{{{{}}}}} {}", number + 1, choice.text));
+    }
+    description
+}
+
+fn apply_dialogue_effect(world: &World, player_entity: usize, npc_entity: usize, speaker: &str, effect: &DialogueEffect, game_output: &mut String) {
+    match effect {
+        DialogueEffect::Give(item) => {
+            let npc_items = world.borrow_component::<InventoryComponent>().unwrap()[npc_entity]
+                .as_ref()
+                .map(|inventory| inventory.items())
+                .unwrap_or_default();
+            let name = item.to_string().to_lowercase();
+            let item_entity = match find_item(world, &npc_items, *item) {
+                Some(item_entity) => item_entity,
+                None => {
+                    game_output.push_str(&format!("\n{} pats their pockets, but there's no {} left to give me", speaker, name));
+                    return;
+                }
+            };
+            match give_item(world, npc_entity, player_entity, item_entity) {
+                Ok(()) => game_output.push_str(&format!("\n{} hands me the {}", speaker, name)),
+                // Whatever doesn't fit ends up at my feet
+                Err(_) => {
+                    drop_item(world, npc_entity, item_entity);
+                    game_output.push_str(&format!("\n{} hands me the {}, but I can't carry it so I set it down", speaker, name));
+                }
+            }
+        }
+        DialogueEffect::SetFlag(flag) => {
+            world.borrow_resource_mut::<Flags>().unwrap().set.insert(flag.clone());
+        }
+        DialogueEffect::StartEvent { after, event } => {
+            world.borrow_resource_mut::<Scheduler>().unwrap().schedule_in(*after, event.clone());
+        }
+    }
+}
+
+// Starts a conversation with whoever is named, the dialogue system takes the input from then on
+fn update_talk_system(world: &World, command_vec: &[&str], game_output: &mut String) {
+    let mut iter = command_vec.iter();
+    if !matches!(iter.next().map(|command| Command::from_str(command)), Some(Ok(Command::Talk))) {
+        return;
+    }
+    let player_entity = get_player(world);
+    let name = match (iter.next(), iter.next()) {
+        (Some(&"to"), Some(name)) => *name,
+        _ => {
+            game_output.push_str("I need to say who I want to talk to, {{Talk}} to <name>");
+            return;
+        }
+    };
+    let npc_entity = match find_named_entity(world, name, player_entity) {
+        Some(npc_entity) => npc_entity,
+        None => {
+            game_output.push_str(&format!("There's no one called {} here", name));
+            return;
+        }
+    };
+    let names = world.borrow_component::<NameComponent>().unwrap();
+    let speaker = names[npc_entity].as_ref().map_or(name, |name| name.name.as_str());
+    let dialogues = world.borrow_resource::<Dialogues>().unwrap();
+    let dialogue = match dialogues.by_npc.get(&speaker.to_lowercase()) {
+        Some(dialogue) => dialogue,
+        None => {
+            game_output.push_str(&format!("The {} doesn't have much to say", speaker));
+            return;
+        }
+    };
+    game_output.push_str(&describe_node(world, player_entity, speaker, &dialogue.nodes[&dialogue.start]));
+    world.borrow_resource_mut::<Conversation>().unwrap().active = Some((npc_entity, dialogue.start.clone()));
+    set_state(world, GameState::Dialogue);
+}
+
+// Takes the input while a conversation is going, the player answers with the number of a choice
+fn dialogue_system(world: &World, command_vec: &[&str], game_output: &mut String) {
+    game_output.push_str(&answer_dialogue(world, command_vec, get_player(world)));
+}
+
+fn answer_dialogue(world: &World, command_vec: &[&str], player_entity: usize) -> String {
+    let (npc_entity, node_name) = match world.borrow_resource::<Conversation>().unwrap().active.clone() {
+        Some(active) => active,
+        None => return String::new(),
+    };
+    let names = world.borrow_component::<NameComponent>().unwrap();
+    let speaker = names[npc_entity].as_ref().map_or("They", |name| name.name.as_str());
+    let dialogues = world.borrow_resource::<Dialogues>().unwrap();
+    let dialogue = &dialogues.by_npc[&speaker.to_lowercase()];
+    let node = &dialogue.nodes[&node_name];
+    let choices = available_choices(world, player_entity, node);
+
+    let picked = command_vec.first().and_then(|number| number.parse::<usize>().ok()).filter(|number| (1..=choices.len()).contains(number));
+    let choice = match picked {
+        Some(number) => choices[number - 1],
+        None => {
+            return format!("I should answer {}, I could say {{{{1}}}} to {{{{{}}}}}\n{}", speaker, choices.len(), describe_node(world, player_entity, speaker, node));
+        }
+    };
+    let mut game_output = format!("I say \"{}\"", choice.text);
+    for effect in &choice.effects {
+        apply_dialogue_effect(world, player_entity, npc_entity, speaker, effect, &mut game_output);
+    }
+    let mut conversation = world.borrow_resource_mut::<Conversation>().unwrap();
+    match &choice.next {
+        Some(next) => {
+            game_output.push('\n');
+            game_output.push_str(&describe_node(world, player_entity, speaker, &dialogue.nodes[next]));
+            conversation.active = Some((npc_entity, next.clone()));
+        }
+        None => {
+            conversation.active = None;
+            set_state(world, GameState::Playing);
+        }
+    }
+    game_output
+}
+
+// Talking to the people I meet, the flags their answers set can be checked by any condition
+pub struct DialoguePlugin;
+
+impl Plugin<GameState> for DialoguePlugin {
+    fn build(&self, app: &mut App<GameState>) {
+        app.add_startup_system(load_dialogue_system)
+            .add_system(GameState::Playing, update_talk_system)
+            .add_system(GameState::Dialogue, dialogue_system)
+            .add_command("Talk", "If there's anyone around I can {{Talk}} to them");
+    }
+}
+
+fn load_dialogue_system(world: &mut World) {
+    world.add_resource(Flags::default());
+    world.add_resource(Conversation::default());
+    let dialogues = match load_dialogues(include_str!("dialogue.txt")) {
+        Ok(dialogues) => dialogues,
+        Err(err) => {
+            println!("Failed to load dialogue.txt, {}", err);
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = validate_dialogues(world, get_player(world), &dialogues) {
+        println!("Failed to load dialogue.txt, {}", err);
+        std::process::exit(1);
+    }
+    world.add_resource(dialogues);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{held_kinds, run, setup_world};
+
+    #[test]
+    fn dialogue_choices_apply_their_effects() {
+        let (world, player_entity) = setup_world();
+        run(&world, "move right");
+        let mut output = String::new();
+        update_talk_system(&world, &["talk", "to", "walt"], &mut output);
+        assert!(output.starts_with("Walt: \"Jakob!"));
+        assert!(world.borrow_resource::<Conversation>().unwrap().active.is_some());
+
+        // Anything but a number keeps the conversation where it is
+        assert!(answer_dialogue(&world, &["move", "back"], player_entity).starts_with("I should answer Walt"));
+        answer_dialogue(&world, &["2"], player_entity);
+        let output = answer_dialogue(&world, &["1"], player_entity);
+        assert!(output.ends_with("Walt hands me the canister"));
+        assert!(world.borrow_resource::<Conversation>().unwrap().active.is_none());
+        assert!(world.borrow_resource::<Flags>().unwrap().set.contains("walt_gave_gasoline"));
+        assert_eq!(held_kinds(&world, player_entity), vec![Item::Lighter, Item::Watch, Item::Canister]);
+
+        // The offer isn't made twice
+        let mut output = String::new();
+        update_talk_system(&world, &["talk", "to", "walt"], &mut output);
+        assert!(!output.contains("anything to spare"));
+    }
+
+    #[test]
+    fn dialogue_choices_must_lead_to_a_node() {
+        let (world, player_entity) = setup_world();
+        let dialogues = load_dialogues("node|walt|start|\"Hello\"\nchoice|walt|start|\"Hi\"|to=nowhere").unwrap();
+        assert_eq!(
+            validate_dialogues(&world, player_entity, &dialogues),
+            Err(String::from("line 2, walt has no node called \"nowhere\""))
+        );
+        assert!(load_dialogues("choice|walt|start|\"Hi\"").is_err());
+    }
+}
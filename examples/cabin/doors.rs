@@ -0,0 +1,380 @@
+use crate::{GameEntities, GameState};
+use crate::areas::{ExitsComponent, PlaceComponent, get_place};
+use crate::items::{InventoryComponent, Item, NameComponent, find_item, get_item_kind, use_item_charge, wear_item};
+use crate::map::LocationComponent;
+use crate::player::{Command, get_player};
+use crate::survival::{GameEvent, LIGHTER_WARMTH, Scheduler, WarmthComponent};
+use ecs_small::{App, Bundle, Component, Message, Plugin, Priority, Reflect, RenderQueue, World};
+
+// Things that can be opened and shut such as the trash can, anything in their inventory is only reachable when open
+#[derive(Component, Reflect)]
+#[component(name = "openable", storage = "sparse")]
+pub struct OpenableComponent {
+    pub is_open: bool,
+}
+
+// Keeps an openable thing shut until it's unlocked with the key item
+#[derive(Component, Reflect)]
+#[component(name = "lockable", storage = "sparse")]
+pub struct LockableComponent {
+    #[reflect(skip)]
+    pub key_item: Item,
+    pub is_locked: bool,
+}
+
+// Containers standing at a place, like items_at every container in a room is at hand
+pub fn containers_at(world: &World, place: PlaceComponent, location: LocationComponent) -> Vec<usize> {
+    let openables = match world.borrow_component::<OpenableComponent>() {
+        Some(openables) => openables,
+        None => return Vec::new(),
+    };
+    let locations = world.borrow_component::<LocationComponent>().unwrap();
+    openables
+        .iter()
+        .map(|(entity, _)| entity)
+        .filter(|entity| get_place(world, *entity) == Some(place))
+        .filter(|entity| place.room.is_some() || locations[*entity] == Some(location))
+        .collect()
+}
+
+// The container with the name at the same place as the entity
+fn find_container(world: &World, name: &str, entity: usize) -> Option<usize> {
+    let place = get_place(world, entity)?;
+    let location = world.borrow_component::<LocationComponent>()?[entity]?;
+    let names = world.borrow_component::<NameComponent>()?;
+    containers_at(world, place, location)
+        .into_iter()
+        .find(|container| names[*container].as_ref().is_some_and(|container_name| container_name.name == name))
+}
+
+#[derive(Component, Reflect)]
+#[component(name = "door", storage = "sparse")]
+pub struct DoorComponent {
+    pub is_frozen: bool,
+    pub is_window_intact: bool,
+    pub is_gasolined: bool
+}
+
+impl DoorComponent {
+    pub fn new() -> Self {
+        DoorComponent {
+            is_frozen: true,
+            is_window_intact: true,
+            is_gasolined: false,
+        }
+    }
+    pub fn is_frozen(&self) -> bool {
+        self.is_frozen
+    }
+    pub fn is_gasolined(&self) -> bool {
+        self.is_gasolined
+    }
+    pub fn set_is_frozen(&mut self, frozen: bool) {
+        self.is_frozen = frozen;
+    }
+    pub fn set_is_window_intact(&mut self, window_intact: bool) {
+        self.is_window_intact = window_intact;
+    }
+    pub fn set_is_gasolined(&mut self, gasolined: bool) {
+        self.is_gasolined = gasolined;
+    }
+}
+
+// What happens to the door answers for the player, so it replaces whatever the player system had to say about the item
+fn update_door_system(world: &World, command_vec: &[&str], _: &mut String) {
+    if command_vec.is_empty() {
+        return;
+    }
+    let player_entity = get_player(world);
+    let door_entity = match world.borrow_resource::<GameEntities>().unwrap().door {
+        Some(door_entity) => door_entity,
+        None => return,
+    };
+    let locations = world.borrow_component::<LocationComponent>().unwrap();
+    let inventories = world.borrow_component::<InventoryComponent>().unwrap();
+    let held = &inventories[player_entity].as_ref().expect("Player can't carry anything").items();
+    let player_location = locations[player_entity].as_ref().expect("Player does not have a location");
+    let door_location = locations[door_entity].as_ref().expect("Door does not have a location");
+    let player_place = get_place(world, player_entity).expect("Player is not in the world");
+    let door_place = get_place(world, door_entity).expect("Door is not in the world");
+
+    // Using one item on another is handled by the player, not the door
+    if !player_location.eq(door_location) || player_place != door_place || command_vec.contains(&"on") {
+        return;
+    }
+    let mut iter = command_vec.iter();
+    let command = Command::from_str(iter.next().unwrap_or(&"Command Required to act {{Move, Check, Use}}"));
+
+    if let Ok(Command::Use) = command {
+        if let Ok(item) = Item::from_str(iter.next().unwrap_or(&"Item required to use, maybe I should {{Check Pocket}}")) {
+            let respond = |text: &str| {
+                let message = Message::narration(text).from(player_entity).with_priority(Priority::High);
+                world.borrow_resource_mut::<RenderQueue>().unwrap().push(message);
+            };
+            let mut doors = world.borrow_component_mut::<DoorComponent>().unwrap();
+            let door = doors.get_mut(door_entity).expect("Could not find a door component");
+            let item_entity = match find_item(world, held, item) {
+                Some(item_entity) => item_entity,
+                None => return,
+            };
+            match item {
+                Item::Canister => {
+                    if door.is_frozen() {
+                        if let Err(message) = use_item_charge(world, item_entity) {
+                            respond(&message);
+                            return;
+                        }
+                        respond("The contents of the canister were poured on the doorknob");
+                        door.set_is_gasolined(true);
+                        // It won't stay on the doorknob for long in this wind
+                        world.borrow_resource_mut::<Scheduler>().unwrap().schedule_in(10, GameEvent::GasolineEvaporated);
+                    } else {
+                        respond("The doorknob doesn't need any more gasoline");
+                    }
+                }
+                // Once the door has thawed the lighter is only good for warming my hands, the player system sees to that
+                Item::Lighter if door.is_frozen() => {
+                    if let Err(message) = use_item_charge(world, item_entity) {
+                        respond(&message);
+                        return;
+                    }
+                    if door.is_gasolined() {
+                        respond(
+                            "Looks like I can melt the doorknob now\n\
+                             *Lights doorknob aflame*\n\
+                             Looks like the doorknob has thawed and I can {{Go}} through the door",
+                        );
+                        door.set_is_frozen(false);
+                        // The flame burns off what's left of the gasoline
+                        door.set_is_gasolined(false);
+                    } else {
+                        let mut warmths = world.borrow_component_mut::<WarmthComponent>().unwrap();
+                        if let Some(warmth) = warmths[player_entity].as_mut() {
+                            warmth.warm_up(LIGHTER_WARMTH);
+                        }
+                        respond(
+                            "I warm my hands over the flame for a moment\n\
+                             I'll run out of fuel in my lighter before I finish melting the doorknob",
+                        );
+                    }
+                }
+                Item::Rock => {
+                    if let Err(message) = wear_item(world, item_entity) {
+                        respond(&message);
+                        return;
+                    }
+                    respond(
+                        "I can smash the window using this rock\n\
+                         *Smashes window with rock*\n\
+                         There's a hole in the window I can {{Go}} through now",
+                    );
+                    door.set_is_window_intact(false);
+                }
+                _ => {
+                }
+            }
+        }
+    }
+}
+
+// Opening, closing and unlocking containers as well as taking what's inside them
+fn update_container_system(world: &World, command_vec: &[&str], game_output: &mut String) {
+    let mut iter = command_vec.iter();
+    let command = match iter.next().map(|command| Command::from_str(command)) {
+        Some(Ok(command @ (Command::Open | Command::Close | Command::Unlock | Command::Look))) => command,
+        _ => return,
+    };
+    let player_entity = get_player(world);
+    // look in <name>
+    if command == Command::Look && iter.next().map(|word| word.to_lowercase()).as_deref() != Some("in") {
+        game_output.push_str("I could {{Look}} in something if I find somewhere things might be kept");
+        return;
+    }
+    let name = iter.map(|word| word.to_lowercase()).collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        game_output.push_str("I need to say what I want to do that to");
+        return;
+    }
+    let container_entity = match find_container(world, &name, player_entity) {
+        Some(container_entity) => container_entity,
+        None => {
+            game_output.push_str(&format!("I don't see a {} here", name));
+            return;
+        }
+    };
+    let mut openables = world.borrow_component_mut::<OpenableComponent>().unwrap();
+    let openable = openables.get_mut(container_entity).expect("Containers can be opened");
+    let mut lockables = world.borrow_component_mut::<LockableComponent>();
+    let lockable = lockables.as_mut().and_then(|lockables| lockables.get_mut(container_entity));
+
+    match command {
+        Command::Open if openable.is_open => game_output.push_str(&format!("The {} is already open", name)),
+        Command::Open => match lockable {
+            Some(lockable) if lockable.is_locked => game_output.push_str(&format!(
+                "The {} is locked, I'll need the {} to {{{{Unlock}}}} it",
+                name,
+                lockable.key_item.to_string().to_lowercase()
+            )),
+            _ => {
+                openable.is_open = true;
+                let inventories = world.borrow_component::<InventoryComponent>().unwrap();
+                match inventories[container_entity].as_ref() {
+                    Some(inventory) if !inventory.is_empty() => {
+                        game_output.push_str(&format!("I open the {}, inside there's {{{{{}}}}}", name, inventory.list()));
+                    }
+                    _ => game_output.push_str(&format!("I open the {}, there's nothing inside", name)),
+                }
+            }
+        },
+        Command::Close if !openable.is_open => game_output.push_str(&format!("The {} is already closed", name)),
+        Command::Close => {
+            openable.is_open = false;
+            game_output.push_str(&format!("I close the {}", name));
+        }
+        Command::Unlock => match lockable {
+            Some(lockable) if lockable.is_locked => {
+                let inventories = world.borrow_component::<InventoryComponent>().unwrap();
+                if inventories[player_entity].as_ref().map_or(0, |inventory| inventory.quantity(lockable.key_item)) == 0 {
+                    game_output.push_str("I don't have anything to unlock it with");
+                    return;
+                }
+                lockable.is_locked = false;
+                game_output.push_str(&format!("I unlock the {} with the {}", name, lockable.key_item.to_string().to_lowercase()));
+            }
+            _ => game_output.push_str(&format!("The {} isn't locked", name)),
+        },
+        _ if !openable.is_open => game_output.push_str(&format!("The {} is closed, I'd have to {{{{Open}}}} it first", name)),
+        _ => {
+            // Everything that fits is moved from the container into my pocket
+            let mut inventories = world.borrow_component_mut::<InventoryComponent>().unwrap();
+            let contents = inventories[container_entity].as_ref().map(|inventory| inventory.items()).unwrap_or_default();
+            if contents.is_empty() {
+                game_output.push_str(&format!("There's nothing in the {}", name));
+                return;
+            }
+            let mut lines = Vec::new();
+            for item_entity in contents {
+                let item = get_item_kind(world, item_entity).map_or("something", |kind| kind.to_string());
+                match inventories[player_entity].as_mut().expect("Player can't carry anything").insert(world, item_entity) {
+                    Ok(()) => {
+                        inventories[container_entity].as_mut().unwrap().remove(item_entity);
+                        lines.push(format!("Looks like there's {} in the {}. I'll hold on to it for later", item, name));
+                    }
+                    Err(reason) => lines.push(format!("Looks like there's {} in the {}. {}", item, name, reason)),
+                }
+            }
+            game_output.push_str(&lines.join("\n"));
+        }
+    }
+}
+
+// The frozen front door and anything else that opens, closes or locks
+pub struct DoorsPlugin;
+
+impl Plugin<GameState> for DoorsPlugin {
+    fn build(&self, app: &mut App<GameState>) {
+        app.register_reflect::<DoorComponent>()
+            .add_startup_system(spawn_door_system)
+            .add_startup_system(validate_exits_system)
+            .add_system(GameState::Playing, update_container_system)
+            .add_system(GameState::Playing, update_door_system)
+            .add_command("Open", "Anything that holds things I can {{Open}}, {{Close}}, {{Unlock}} or {{Look}} in")
+            .add_command("Close", "")
+            .add_command("Unlock", "")
+            .add_command("Look", "");
+    }
+}
+
+#[derive(Bundle)]
+struct DoorBundle {
+    location: LocationComponent,
+    place: PlaceComponent,
+    door: DoorComponent,
+}
+
+fn spawn_door_system(world: &mut World) {
+    let outside = get_place(world, get_player(world)).expect("The player hasn't been placed").area;
+    let door_entity = world.spawn(DoorBundle {
+        location: LocationComponent{x: 2, y: 2},
+        place: PlaceComponent { area: outside, room: None },
+        door: DoorComponent::new(),
+    });
+    world.borrow_resource_mut::<GameEntities>().unwrap().door = Some(door_entity);
+}
+
+// Runs every exit's condition once the door is in place, so mistakes in the area files are caught before the game starts
+fn validate_exits_system(world: &mut World) {
+    let player_entity = get_player(world);
+    let exits = world.borrow_component::<ExitsComponent>().unwrap();
+    for exit in exits.iter().flatten().flat_map(|exits| exits.exits.iter()) {
+        if let Some(Err(err)) = exit.condition.as_ref().map(|condition| condition.evaluate_bool(world, player_entity)) {
+            println!("Failed to load the exit \"{}\", {}", exit.keyword, err);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dev::{give_command, teleport_command};
+    use crate::player::update_player_system;
+    use crate::tests::{charges_of, held, held_kinds, setup_world, warmth_of};
+
+    // Runs a turn of the player and door systems, the way the game does when the player is at the door
+    fn run_at_door(world: &World, command: &str) -> String {
+        let input: Vec<&str> = command.split_ascii_whitespace().collect();
+        let mut output = String::new();
+        update_player_system(world, &input, &mut output);
+        update_door_system(world, &input, &mut output);
+        let mut queue = world.borrow_resource_mut::<RenderQueue>().unwrap();
+        if !output.is_empty() {
+            queue.push(Message::narration(output));
+        }
+        queue.compose()
+    }
+
+    #[test]
+    fn the_lighter_melts_the_door_once() {
+        let (mut world, player_entity) = setup_world();
+        give_command(&mut world, &["canister"]).unwrap();
+        teleport_command(&mut world, &["2", "2"]).unwrap();
+        world.borrow_resource_mut::<RenderQueue>().unwrap().compose();
+        let lighter = held(&world, player_entity, Item::Lighter);
+        let door_entity = world.borrow_resource::<GameEntities>().unwrap().door.unwrap();
+        let door = |world: &World| {
+            let doors = world.borrow_component::<DoorComponent>().unwrap();
+            let door = doors.get(door_entity).unwrap();
+            (door.is_frozen(), door.is_gasolined())
+        };
+
+        assert_eq!(run_at_door(&world, "use canister"), "The contents of the canister were poured on the doorknob");
+        assert!(run_at_door(&world, "use lighter").starts_with("Looks like I can melt the doorknob now"));
+        assert_eq!(door(&world), (false, false));
+        assert_eq!(charges_of(&world, lighter), 4);
+
+        // There's nothing left to melt, so the flame only warms my hands
+        world.borrow_component_mut::<WarmthComponent>().unwrap()[player_entity].as_mut().unwrap().warmth = 50.0;
+        assert_eq!(run_at_door(&world, "use lighter"), "I cup my hands around the lighter's flame, it helps a little");
+        assert_eq!(door(&world), (false, false));
+        assert_eq!(charges_of(&world, lighter), 3);
+        assert_eq!(warmth_of(&world, player_entity), 50.0 + LIGHTER_WARMTH);
+    }
+
+    fn run_containers(world: &World, command: &str) -> String {
+        let mut output = String::new();
+        update_container_system(world, &command.split_ascii_whitespace().collect::<Vec<_>>(), &mut output);
+        output
+    }
+
+    #[test]
+    fn containers_have_to_be_opened_to_take_from() {
+        let (world, player_entity) = setup_world();
+        assert_eq!(run_containers(&world, "look in mailbox"), "The mailbox is closed, I'd have to {{Open}} it first");
+        assert_eq!(run_containers(&world, "open mailbox"), "I open the mailbox, inside there's {{Key}}");
+        run_containers(&world, "look in mailbox");
+        assert_eq!(held_kinds(&world, player_entity), vec![Item::Lighter, Item::Watch, Item::Key]);
+        assert_eq!(run_containers(&world, "look in mailbox"), "There's nothing in the mailbox");
+        assert_eq!(run_containers(&world, "open trash can"), "I don't see a trash can here");
+    }
+}
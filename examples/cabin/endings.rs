@@ -0,0 +1,208 @@
+use crate::{GameResult, GameState, set_state};
+use crate::conditions::Condition;
+use crate::map::{MapField, MapLineScanner, MapParseError, MapValue};
+use crate::player::get_player;
+use ecs_small::{App, Plugin, Stage, World};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum EndingCategory {
+    Good,
+    Bad,
+    Death,
+}
+
+impl EndingCategory {
+    fn from_str(s: &str) -> Result<EndingCategory, &str> {
+        match s {
+            "good" => Ok(EndingCategory::Good),
+            "bad" => Ok(EndingCategory::Bad),
+            "death" => Ok(EndingCategory::Death),
+            _ => Err("Failed to find the ending category"),
+        }
+    }
+}
+
+// One of the ways the game can finish, declared in endings.txt
+#[derive(Debug)]
+pub struct Ending {
+    name: String,
+    condition: Condition,
+    category: EndingCategory,
+    score: u32,
+    priority: i64,
+    text: String,
+}
+
+// Every ending the game can finish with, highest priority first
+pub struct Endings {
+    pub endings: Vec<Ending>,
+}
+
+// What the ending system hands back once the game is over
+#[derive(Debug, PartialEq)]
+pub struct GameOutcome {
+    pub name: String,
+    pub category: EndingCategory,
+    score: u32,
+    text: String,
+}
+
+// Endings are written one per line as
+//     ending|<name>|when="<condition>"|category=<good, bad or death>|score=<number>|priority=<number>|text="<ending text>"
+// priority and score default to 0, the rest are required
+fn load_endings(contents: &str) -> Result<Vec<Ending>, MapParseError> {
+    let mut endings = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let mut scanner = MapLineScanner::new(index + 1, line);
+        let fields = scanner.parse_fields()?;
+        let name = match fields.as_slice() {
+            [] => continue,
+            [MapField { value: MapValue::Word(kind), .. }, MapField { value: MapValue::Word(name), .. }, ..] if kind == "ending" => name.clone(),
+            [first, ..] => return Err(scanner.error_at(first.column, "an ending is written as ending|<name>|when=\"<condition>\"|...")),
+        };
+        let mut condition = None;
+        let mut category = None;
+        let mut score = 0;
+        let mut priority = 0;
+        let mut text = None;
+        for field in &fields[2..] {
+            let (property, value) = match &field.value {
+                MapValue::Property(property, value) => (property.as_str(), value.as_ref()),
+                _ => return Err(scanner.error_at(field.column, "expected a property like category=good")),
+            };
+            match (property, value) {
+                ("when", MapValue::Text(when)) => {
+                    // The condition starts after `when="`
+                    let offset = field.column + property.len() + 1;
+                    condition = Some(Condition::parse(when).map_err(|err| scanner.error_at(offset + err.column, err.reason))?);
+                }
+                ("category", MapValue::Word(word)) => match EndingCategory::from_str(word) {
+                    Ok(found) => category = Some(found),
+                    Err(_) => return Err(scanner.error_at(field.column, format!("\"{}\" is not good, bad or death", word))),
+                },
+                ("score", MapValue::Word(word)) => {
+                    score = word.parse().map_err(|_| scanner.error_at(field.column, "the score must be a whole number"))?;
+                }
+                ("priority", MapValue::Word(word)) => {
+                    priority = word.parse().map_err(|_| scanner.error_at(field.column, "the priority must be a whole number"))?;
+                }
+                ("text", MapValue::Text(found)) => text = Some(found.clone()),
+                ("when" | "text", _) => return Err(scanner.error_at(field.column, format!("{} must be quoted text", property))),
+                ("category" | "score" | "priority", _) => return Err(scanner.error_at(field.column, format!("{} must be a single word", property))),
+                _ => return Err(scanner.error_at(field.column, format!("unknown ending property \"{}\"", property))),
+            }
+        }
+        let missing = |property: &str| scanner.error(format!("ending \"{}\" is missing {}", name, property));
+        endings.push(Ending {
+            condition: condition.ok_or_else(|| missing("when"))?,
+            category: category.ok_or_else(|| missing("category"))?,
+            text: text.ok_or_else(|| missing("text"))?,
+            name,
+            score,
+            priority,
+        });
+    }
+    // Highest priority first, endings with the same priority keep the order they were written in
+    endings.sort_by_key(|ending| std::cmp::Reverse(ending.priority));
+    Ok(endings)
+}
+
+// Runs every ending's condition once so mistakes such as unknown values are caught before the game starts
+fn validate_endings(world: &World, player_entity: usize, endings: &[Ending]) -> Result<(), String> {
+    for ending in endings {
+        ending
+            .condition
+            .evaluate_bool(world, player_entity)
+            .map_err(|err| format!("ending \"{}\", {}", ending.name, err))?;
+    }
+    Ok(())
+}
+
+// Endings are checked highest priority first, the first one whose condition holds finishes the game
+pub fn game_ending_system(world: &World, player_entity: usize, endings: &[Ending]) -> Option<GameOutcome> {
+    let ending = endings
+        .iter()
+        .find(|ending| ending.condition.evaluate_bool(world, player_entity).unwrap_or(false))?;
+    Some(GameOutcome {
+        name: ending.name.clone(),
+        category: ending.category,
+        score: ending.score,
+        text: ending.text.clone(),
+    })
+}
+
+pub fn render_ending(outcome: &GameOutcome) -> String {
+    let category = match outcome.category {
+        EndingCategory::Good => "Good Ending",
+        EndingCategory::Bad => "Bad Ending",
+        EndingCategory::Death => "Death",
+    };
+    format!("{}\n> *Game Over* - {} ({} points)", outcome.text, category, outcome.score)
+}
+
+// Checks endings.txt after every turn, the conditions can look at anything the other plugins set up so it goes last
+pub struct EndingsPlugin;
+
+impl Plugin<GameState> for EndingsPlugin {
+    fn build(&self, app: &mut App<GameState>) {
+        app.add_startup_system(load_endings_system)
+            .add_system_to_stage(GameState::Playing, Stage::Last, playing_ending_system)
+            .add_system_to_stage(GameState::Dialogue, Stage::Last, playing_ending_system);
+    }
+}
+
+fn load_endings_system(world: &mut World) {
+    let endings = match load_endings(include_str!("endings.txt")) {
+        Ok(endings) => endings,
+        Err(err) => {
+            println!("Failed to load endings.txt, {}", err);
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = validate_endings(world, get_player(world), &endings) {
+        println!("Failed to load endings.txt, {}", err);
+        std::process::exit(1);
+    }
+    world.add_resource(Endings { endings });
+}
+
+fn playing_ending_system(world: &World, _: &[&str], _: &mut String) {
+    let outcome = game_ending_system(world, get_player(world), &world.borrow_resource::<Endings>().unwrap().endings);
+    if let Some(outcome) = outcome {
+        world.borrow_resource_mut::<GameResult>().unwrap().outcome = Some(outcome);
+        set_state(world, GameState::GameOver);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::setup_world;
+
+    #[test]
+    fn endings_are_checked_highest_priority_first() {
+        let (world, player_entity) = setup_world();
+        let endings = load_endings(
+            "ending|low|when=\"true\"|category=bad|priority=1|text=\"Low\"\n\
+             ending|high|when=\"player.alive\"|category=good|score=10|priority=5|text=\"High\"\n\
+             ending|also_low|when=\"true\"|category=bad|priority=1|text=\"Also low\"\n\
+             ending|never|when=\"not player.alive\"|category=death|priority=9|text=\"Never\"",
+        )
+        .unwrap();
+        let names: Vec<&str> = endings.iter().map(|ending| ending.name.as_str()).collect();
+        assert_eq!(names, vec!["never", "high", "low", "also_low"]);
+        assert_eq!(
+            game_ending_system(&world, player_entity, &endings),
+            Some(GameOutcome {
+                name: String::from("high"),
+                category: EndingCategory::Good,
+                score: 10,
+                text: String::from("High"),
+            })
+        );
+
+        // Mistakes inside a condition are reported from the start of the line
+        let error = load_endings("ending|x|when=\"a and\"|category=bad|text=\"X\"").err().unwrap().to_string();
+        assert_eq!(error, "line 1, column 21: the condition ended early");
+    }
+}
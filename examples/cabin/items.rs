@@ -0,0 +1,624 @@
+use crate::GameState;
+use crate::areas::{PlaceComponent, get_place};
+use crate::map::{LocationComponent, MapField, MapLineScanner, MapParseError, MapValue};
+use ecs_small::{App, Component, Plugin, Reflect, World};
+use std::collections::HashMap;
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Item {
+    Canister,
+    Lighter,
+    Watch,
+    Rock,
+    Key,
+}
+
+impl Item {
+    pub fn from_str(s: &str) -> Result<Item, &str> {
+        match s {
+            "canister" => Ok(Item::Canister),
+            "lighter" => Ok(Item::Lighter),
+            "watch" => Ok(Item::Watch),
+            "rock" => Ok(Item::Rock),
+            "key" => Ok(Item::Key),
+            _ => Err("Failed to find the item")
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Item::Canister => "Canister",
+            Item::Lighter => "Lighter",
+            Item::Watch => "Watch",
+            Item::Rock => "Rock",
+            Item::Key => "Key"
+        }
+    }
+}
+
+// Every item in the world is an entity of its own, lying on a tile or held by someone
+#[derive(Debug, Component)]
+#[component(name = "item")]
+struct ItemComponent {
+    kind: Item,
+}
+
+// Items of the same kind held together, each one is still its own entity with its own charges
+struct ItemStack {
+    kind: Item,
+    items: Vec<usize>,
+}
+
+// Items held by something, stacks are kept in the order they were first picked up
+// Either limit can be left off, a slot holds one stack however many items are in it
+#[derive(Default, Component)]
+#[component(name = "inventory")]
+pub struct InventoryComponent {
+    stacks: Vec<ItemStack>,
+    max_weight: Option<u32>,
+    max_slots: Option<usize>,
+}
+
+impl InventoryComponent {
+    pub fn with_capacity(max_weight: Option<u32>, max_slots: Option<usize>) -> Self {
+        InventoryComponent { stacks: Vec::new(), max_weight, max_slots }
+    }
+
+    // Every held item entity, stack by stack
+    pub fn items(&self) -> Vec<usize> {
+        self.stacks.iter().flat_map(|stack| stack.items.iter().copied()).collect()
+    }
+
+    pub fn quantity(&self, kind: Item) -> usize {
+        self.stacks.iter().find(|stack| stack.kind == kind).map_or(0, |stack| stack.items.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stacks.is_empty()
+    }
+
+    // The combined weight of everything held, see the weight property in items.txt
+    fn weight(&self, world: &World) -> u32 {
+        self.stacks.iter().map(|stack| item_weight(world, stack.kind) * stack.items.len() as u32).sum()
+    }
+
+    // Fails with the reason the item doesn't fit, nothing is added in that case
+    pub fn insert(&mut self, world: &World, item_entity: usize) -> Result<(), String> {
+        let kind = get_item_kind(world, item_entity).ok_or("That isn't something I can carry")?;
+        let name = kind.to_string().to_lowercase();
+        if let Some(max_weight) = self.max_weight {
+            if self.weight(world) + item_weight(world, kind) > max_weight {
+                return Err(format!("The {} is too heavy to carry with everything else", name));
+            }
+        }
+        match self.stacks.iter_mut().find(|stack| stack.kind == kind) {
+            Some(stack) => stack.items.push(item_entity),
+            None => {
+                if self.max_slots.is_some_and(|max_slots| self.stacks.len() >= max_slots) {
+                    return Err(format!("There's no room left for the {}", name));
+                }
+                self.stacks.push(ItemStack { kind, items: vec![item_entity] });
+            }
+        }
+        Ok(())
+    }
+
+    // Takes the item out, false if it wasn't held here
+    pub fn remove(&mut self, item_entity: usize) -> bool {
+        for (index, stack) in self.stacks.iter_mut().enumerate() {
+            if let Some(position) = stack.items.iter().position(|held| *held == item_entity) {
+                stack.items.remove(position);
+                if stack.items.is_empty() {
+                    self.stacks.remove(index);
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    // Lists the stacks such as "Lighter, Rock x2", for whoever holds them to phrase as they like
+    pub fn list(&self) -> String {
+        self.stacks
+            .iter()
+            .map(|stack| match stack.items.len() {
+                1 => stack.kind.to_string().to_string(),
+                quantity => format!("{} x{}", stack.kind.to_string(), quantity),
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+// What other entities are called when the player talks about them, such as "give rock to <name>"
+#[derive(Component, Reflect)]
+#[component(name = "name")]
+pub struct NameComponent {
+    pub name: String,
+}
+
+// Uses left in a consumable item, such as the gasoline in the canister or the lighter's fuel
+#[derive(Component, Reflect)]
+#[component(name = "charges")]
+pub struct ChargesComponent {
+    pub charges: u32,
+    max_charges: u32,
+}
+
+impl ChargesComponent {
+    fn use_charge(&mut self) -> bool {
+        if self.charges == 0 {
+            return false;
+        }
+        self.charges -= 1;
+        true
+    }
+
+    fn refill(&mut self, amount: u32) {
+        self.charges = (self.charges + amount).min(self.max_charges);
+    }
+}
+
+// How much more wear an item can take before it breaks
+#[derive(Component, Reflect)]
+#[component(name = "durability")]
+struct DurabilityComponent {
+    durability: u32,
+}
+
+impl DurabilityComponent {
+    fn wear(&mut self) {
+        self.durability = self.durability.saturating_sub(1);
+    }
+
+    fn is_broken(&self) -> bool {
+        self.durability == 0
+    }
+}
+
+// What a freshly spawned item of a kind starts with, items without charges or durability never run out
+#[derive(Default, Clone)]
+struct ItemDefinition {
+    description: Option<String>,
+    charges: Option<u32>,
+    durability: Option<u32>,
+    weight: Option<u32>,
+}
+
+// Using the `from` item on `item` moves one charge out of `from` and adds `amount` charges to `item`
+struct Refill {
+    item: Item,
+    from: Item,
+    amount: u32,
+}
+
+// Loaded from items.txt and kept as a resource so items can be spawned at any time
+#[derive(Default)]
+struct ItemCatalog {
+    definitions: HashMap<Item, ItemDefinition>,
+    refills: Vec<Refill>,
+}
+
+impl ItemCatalog {
+    fn find_refill(&self, item: Item, from: Item) -> Option<&Refill> {
+        self.refills.iter().find(|refill| refill.item == item && refill.from == from)
+    }
+}
+
+// Items files have two kinds of lines
+//     item|<name>|"<description>"|charges=<number>|durability=<number>|weight=<number>
+//     refill|<item>|<from item>|amount=<number>
+// Items that aren't listed can be used forever
+fn load_items(contents: &str) -> Result<ItemCatalog, MapParseError> {
+    let mut catalog = ItemCatalog::default();
+    for (index, line) in contents.lines().enumerate() {
+        let mut scanner = MapLineScanner::new(index + 1, line);
+        let fields = scanner.parse_fields()?;
+        if fields.is_empty() {
+            continue;
+        }
+        let item_at = |position: usize| -> Result<Item, MapParseError> {
+            match fields.get(position) {
+                Some(MapField { value: MapValue::Word(word), column }) => {
+                    Item::from_str(word).map_err(|_| scanner.error_at(*column, format!("\"{}\" is not an item", word)))
+                }
+                Some(field) => Err(scanner.error_at(field.column, "expected the name of an item")),
+                None => Err(scanner.error("expected the name of an item")),
+            }
+        };
+        let number = |field: &MapField, value: &MapValue| -> Result<u32, MapParseError> {
+            match value {
+                MapValue::Word(word) => word.parse().map_err(|_| scanner.error_at(field.column, "expected a whole number")),
+                _ => Err(scanner.error_at(field.column, "expected a whole number")),
+            }
+        };
+        match &fields[0].value {
+            MapValue::Word(kind) if kind == "item" => {
+                let item = item_at(1)?;
+                let mut definition = ItemDefinition::default();
+                for field in &fields[2..] {
+                    match &field.value {
+                        MapValue::Text(description) => definition.description = Some(description.clone()),
+                        MapValue::Property(name, value) if name == "charges" => definition.charges = Some(number(field, value)?),
+                        MapValue::Property(name, value) if name == "durability" => definition.durability = Some(number(field, value)?),
+                        MapValue::Property(name, value) if name == "weight" => definition.weight = Some(number(field, value)?),
+                        _ => {
+                            return Err(scanner.error_at(
+                                field.column,
+                                "expected a description, charges=<number>, durability=<number> or weight=<number>",
+                            ))
+                        }
+                    }
+                }
+                catalog.definitions.insert(item, definition);
+            }
+            MapValue::Word(kind) if kind == "refill" => {
+                let item = item_at(1)?;
+                let from = item_at(2)?;
+                let amount = match fields.get(3) {
+                    Some(field @ MapField { value: MapValue::Property(name, value), .. }) if name == "amount" => number(field, value)?,
+                    _ => return Err(scanner.error("a refill is written as refill|<item>|<from item>|amount=<number>")),
+                };
+                catalog.refills.push(Refill { item, from, amount });
+            }
+            _ => return Err(scanner.error_at(fields[0].column, "expected item or refill")),
+        }
+    }
+    Ok(catalog)
+}
+
+// How heavy an item of the kind is, items.txt can leave it off for items that weigh 1
+fn item_weight(world: &World, kind: Item) -> u32 {
+    world
+        .borrow_resource::<ItemCatalog>()
+        .and_then(|catalog| catalog.definitions.get(&kind).and_then(|definition| definition.weight))
+        .unwrap_or(DEFAULT_ITEM_WEIGHT)
+}
+
+// Creates an item entity set up with the charges and durability from the ItemCatalog
+pub fn spawn_item(world: &mut World, kind: Item) -> usize {
+    let definition = world
+        .borrow_resource::<ItemCatalog>()
+        .and_then(|catalog| catalog.definitions.get(&kind).cloned())
+        .unwrap_or_default();
+    let item_entity = world.new_entity();
+    world.add_component_to_entity(item_entity, ItemComponent { kind });
+    if let Some(charges) = definition.charges {
+        world.add_component_to_entity(item_entity, ChargesComponent { charges, max_charges: charges });
+    }
+    if let Some(durability) = definition.durability {
+        world.add_component_to_entity(item_entity, DurabilityComponent { durability });
+    }
+    item_entity
+}
+
+pub fn get_item_kind(world: &World, item_entity: usize) -> Option<Item> {
+    world
+        .borrow_component::<ItemComponent>()
+        .and_then(|items| items[item_entity].as_ref().map(|item| item.kind))
+}
+
+// The first of the held items that is of the given kind
+pub fn find_item(world: &World, held: &[usize], kind: Item) -> Option<usize> {
+    held.iter().copied().find(|item_entity| get_item_kind(world, *item_entity) == Some(kind))
+}
+
+// Items lying on the ground at a place, held items aren't placed anywhere
+// Rooms have no tiles so everything in the room is at hand, the location only matters on a map
+pub fn items_at(world: &World, place: PlaceComponent, location: LocationComponent) -> Vec<usize> {
+    let items = world.borrow_component::<ItemComponent>().unwrap();
+    let locations = world.borrow_component::<LocationComponent>().unwrap();
+    (0..items.len())
+        .filter(|entity| items[*entity].is_some() && get_place(world, *entity) == Some(place))
+        .filter(|entity| place.room.is_some() || locations[*entity] == Some(location))
+        .collect()
+}
+
+// Puts a held item down where the holder is standing
+pub fn drop_item(world: &World, holder: usize, item_entity: usize) {
+    let mut inventories = world.borrow_component_mut::<InventoryComponent>().unwrap();
+    if let Some(inventory) = inventories[holder].as_mut() {
+        inventory.remove(item_entity);
+    }
+    let mut places = world.borrow_component_mut::<PlaceComponent>().unwrap();
+    let mut locations = world.borrow_component_mut::<LocationComponent>().unwrap();
+    places[item_entity] = places[holder];
+    locations[item_entity] = match places[holder] {
+        Some(PlaceComponent { room: None, .. }) => locations[holder],
+        _ => None,
+    };
+}
+
+// Moves a held item from one inventory to another, the giver keeps it if the receiver can't take it
+pub fn give_item(world: &World, giver: usize, receiver: usize, item_entity: usize) -> Result<(), String> {
+    let mut inventories = world.borrow_component_mut::<InventoryComponent>().unwrap();
+    if !inventories[giver].as_ref().is_some_and(|inventory| inventory.items().contains(&item_entity)) {
+        return Err(String::from("I don't have that on me right now"));
+    }
+    inventories[receiver].as_mut().ok_or("They can't hold on to anything")?.insert(world, item_entity)?;
+    inventories[giver].as_mut().unwrap().remove(item_entity);
+    Ok(())
+}
+
+// The item's description from items.txt along with how worn or used up it is
+pub fn describe_item(world: &World, item_entity: usize) -> String {
+    let kind = match get_item_kind(world, item_entity) {
+        Some(kind) => kind,
+        None => return String::from("I can't make out what that is"),
+    };
+    let catalog = world.borrow_resource::<ItemCatalog>().unwrap();
+    let mut description = catalog
+        .definitions
+        .get(&kind)
+        .and_then(|definition| definition.description.clone())
+        .unwrap_or_else(|| format!("It's just a {}", kind.to_string().to_lowercase()));
+    if let Some(charges) = world.borrow_component::<ChargesComponent>().and_then(|charges| charges[item_entity].as_ref().map(|charges| (charges.charges, charges.max_charges))) {
+        match charges {
+            (0, _) => description.push_str("\nIt's empty"),
+            (charges, max_charges) => description.push_str(&format!("\nThere's enough left for {} of {} uses", charges, max_charges)),
+        }
+    }
+    let is_broken = world
+        .borrow_component::<DurabilityComponent>()
+        .is_some_and(|durabilities| durabilities[item_entity].as_ref().is_some_and(|durability| durability.is_broken()));
+    if is_broken {
+        description.push_str("\nIt's broken");
+    }
+    description
+}
+
+// Fails with a generic message if the item is broken or has nothing left in it, nothing is used up
+fn check_item_usable(world: &World, item_entity: usize) -> Result<(), String> {
+    let name = get_item_kind(world, item_entity).map_or("item", |kind| kind.to_string()).to_lowercase();
+    if let Some(durabilities) = world.borrow_component::<DurabilityComponent>() {
+        if durabilities[item_entity].as_ref().is_some_and(|durability| durability.is_broken()) {
+            return Err(format!("My {} is broken, it's no use anymore", name));
+        }
+    }
+    if let Some(charges) = world.borrow_component::<ChargesComponent>() {
+        if charges[item_entity].as_ref().is_some_and(|charges| charges.charges == 0) {
+            return Err(format!("My {} is empty", name));
+        }
+    }
+    Ok(())
+}
+
+// Takes a charge from consumable items, items without charges are left as they are
+pub fn use_item_charge(world: &World, item_entity: usize) -> Result<(), String> {
+    check_item_usable(world, item_entity)?;
+    if let Some(mut charges) = world.borrow_component_mut::<ChargesComponent>() {
+        if let Some(charges) = charges[item_entity].as_mut() {
+            charges.use_charge();
+        }
+    }
+    Ok(())
+}
+
+// Wears down breakable items, items without durability are left as they are
+pub fn wear_item(world: &World, item_entity: usize) -> Result<(), String> {
+    check_item_usable(world, item_entity)?;
+    if let Some(mut durabilities) = world.borrow_component_mut::<DurabilityComponent>() {
+        if let Some(durability) = durabilities[item_entity].as_mut() {
+            durability.wear();
+        }
+    }
+    Ok(())
+}
+
+// Uses one item to top up another when items.txt has a refill for the pair
+pub fn refill_item(world: &World, item_entity: usize, from_entity: usize) -> Result<String, String> {
+    let (item, from) = match (get_item_kind(world, item_entity), get_item_kind(world, from_entity)) {
+        (Some(item), Some(from)) => (item, from),
+        _ => return Err(String::from("I can't use those together")),
+    };
+    let amount = match world.borrow_resource::<ItemCatalog>().unwrap().find_refill(item, from) {
+        Some(refill) => refill.amount,
+        None => {
+            return Err(format!(
+                "I can't use the {} on the {}",
+                from.to_string().to_lowercase(),
+                item.to_string().to_lowercase()
+            ))
+        }
+    };
+    let (item, from) = (item.to_string().to_lowercase(), from.to_string().to_lowercase());
+    let no_charges = || format!("My {} doesn't hold anything to top up", item);
+    let is_full = match world.borrow_component::<ChargesComponent>().as_ref().and_then(|charges| charges[item_entity].as_ref()) {
+        Some(charges) => charges.charges == charges.max_charges,
+        None => return Err(no_charges()),
+    };
+    if is_full {
+        return Err(format!("My {} is already full", item));
+    }
+    use_item_charge(world, from_entity)?;
+    let mut charges = world.borrow_component_mut::<ChargesComponent>().ok_or_else(no_charges)?;
+    charges[item_entity].as_mut().ok_or_else(no_charges)?.refill(amount);
+    Ok(format!("I top up the {} from the {}", item, from))
+}
+
+// How much I can carry in my pockets, see the weights in items.txt
+pub const POCKET_MAX_WEIGHT: u32 = 10;
+
+const DEFAULT_ITEM_WEIGHT: u32 = 1;
+
+// Everything the items in items.txt are made from
+pub struct ItemsPlugin;
+
+impl Plugin<GameState> for ItemsPlugin {
+    fn build(&self, app: &mut App<GameState>) {
+        app.register_debug::<ItemComponent>()
+            .register_reflect::<ChargesComponent>()
+            .register_reflect::<DurabilityComponent>()
+            .add_startup_system(load_item_catalog_system);
+    }
+}
+
+fn load_item_catalog_system(world: &mut World) {
+    match load_items(include_str!("items.txt")) {
+        Ok(catalog) => world.add_resource(catalog),
+        Err(err) => {
+            println!("Failed to load items.txt, {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dev::give_command;
+    use crate::tests::{charges_of, held, held_kinds, run, setup_world};
+
+    #[test]
+    fn charges_run_out() {
+        let (world, player_entity) = setup_world();
+        let lighter = held(&world, player_entity, Item::Lighter);
+        for _ in 0..5 {
+            assert_eq!(run(&world, "use lighter"), "I cup my hands around the lighter's flame, it helps a little");
+        }
+        assert_eq!(charges_of(&world, lighter), 0);
+        assert_eq!(run(&world, "use lighter"), "My lighter is empty");
+    }
+
+    #[test]
+    fn items_break_once_worn_down() {
+        let (mut world, player_entity) = setup_world();
+        give_command(&mut world, &["rock"]).unwrap();
+        let rock = held(&world, player_entity, Item::Rock);
+        assert_eq!(wear_item(&world, rock), Ok(()));
+        assert_eq!(world.borrow_component::<DurabilityComponent>().unwrap()[rock].as_ref().unwrap().durability, 0);
+        assert_eq!(wear_item(&world, rock), Err(String::from("My rock is broken, it's no use anymore")));
+        assert!(describe_item(&world, rock).ends_with("It's broken"));
+    }
+
+    #[test]
+    fn the_canister_refills_the_lighter() {
+        let (mut world, player_entity) = setup_world();
+        give_command(&mut world, &["canister"]).unwrap();
+        let (lighter, canister) = (held(&world, player_entity, Item::Lighter), held(&world, player_entity, Item::Canister));
+        assert_eq!(run(&world, "use canister on lighter"), "My lighter is already full");
+        assert_eq!(run(&world, "use canister on watch"), "I can't use the canister on the watch");
+
+        world.borrow_component_mut::<ChargesComponent>().unwrap()[lighter].as_mut().unwrap().charges = 1;
+        assert_eq!(run(&world, "use canister on lighter"), "I top up the lighter from the canister");
+        assert_eq!((charges_of(&world, lighter), charges_of(&world, canister)), (4, 1));
+        // Topping up never goes past the lighter's max
+        assert_eq!(run(&world, "use canister on lighter"), "I top up the lighter from the canister");
+        assert_eq!((charges_of(&world, lighter), charges_of(&world, canister)), (5, 0));
+
+        world.borrow_component_mut::<ChargesComponent>().unwrap()[lighter].as_mut().unwrap().charges = 0;
+        assert_eq!(run(&world, "use canister on lighter"), "My canister is empty");
+        assert_eq!(charges_of(&world, lighter), 0);
+
+        // A refill for an item without charges is turned down rather than taking from the canister
+        world.borrow_resource_mut::<ItemCatalog>().unwrap().refills.push(Refill { item: Item::Watch, from: Item::Canister, amount: 1 });
+        assert_eq!(run(&world, "use canister on watch"), "My watch doesn't hold anything to top up");
+    }
+
+    #[test]
+    fn drop_leaves_the_item_on_the_tile() {
+        let (world, player_entity) = setup_world();
+        run(&world, "drop lighter");
+        assert_eq!(held_kinds(&world, player_entity), vec![Item::Watch]);
+        let place = get_place(&world, player_entity).unwrap();
+        let dropped = items_at(&world, place, LocationComponent { x: 0, y: 0 });
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(get_item_kind(&world, dropped[0]), Some(Item::Lighter));
+
+        run(&world, "check area");
+        assert_eq!(held_kinds(&world, player_entity), vec![Item::Watch, Item::Lighter]);
+        assert!(items_at(&world, place, LocationComponent { x: 0, y: 0 }).is_empty());
+    }
+
+    #[test]
+    fn drop_needs_the_item_to_be_held() {
+        let (world, player_entity) = setup_world();
+        let output = run(&world, "drop rock");
+        assert!(output.starts_with("I don't have that on me"));
+        assert_eq!(held_kinds(&world, player_entity), vec![Item::Lighter, Item::Watch]);
+    }
+
+    #[test]
+    fn examine_describes_held_and_nearby_items() {
+        let (world, player_entity) = setup_world();
+        let output = run(&world, "examine lighter");
+        assert!(output.starts_with("My old brass lighter"));
+        assert!(output.ends_with("There's enough left for 5 of 5 uses"));
+
+        assert_eq!(run(&world, "examine rock"), "I don't see that anywhere around here");
+        run(&world, "move forward");
+        assert!(run(&world, "examine rock").starts_with("A fist sized rock"));
+        // Looking at it doesn't pick it up
+        assert_eq!(held_kinds(&world, player_entity), vec![Item::Lighter, Item::Watch]);
+    }
+
+    #[test]
+    fn give_moves_the_item_to_someone_nearby() {
+        let (mut world, player_entity) = setup_world();
+        let place = get_place(&world, player_entity).unwrap();
+        let stranger = world.new_entity();
+        world.add_component_to_entity(stranger, NameComponent { name: String::from("Ruth") });
+        world.add_component_to_entity(stranger, InventoryComponent::default());
+        world.add_component_to_entity(stranger, LocationComponent { x: 0, y: 1 });
+        world.add_component_to_entity(stranger, place);
+
+        // Ruth is a tile away so he can't be handed anything yet
+        assert_eq!(run(&world, "give watch to ruth"), "There's no one called ruth here");
+        run(&world, "move forward");
+        assert_eq!(run(&world, "give watch to ruth"), "I hand the watch over to Ruth");
+        assert_eq!(held_kinds(&world, player_entity), vec![Item::Lighter]);
+        assert_eq!(held_kinds(&world, stranger), vec![Item::Watch]);
+        assert!(!items_at(&world, place, LocationComponent { x: 0, y: 1 }).iter().any(|item| get_item_kind(&world, *item) == Some(Item::Watch)));
+    }
+
+    #[test]
+    fn inventory_stacks_items_in_the_order_they_arrive() {
+        let (mut world, _) = setup_world();
+        let rocks = [spawn_item(&mut world, Item::Rock), spawn_item(&mut world, Item::Rock)];
+        let lighter = spawn_item(&mut world, Item::Lighter);
+        let mut inventory = InventoryComponent::default();
+        assert!(inventory.is_empty());
+        assert_eq!(inventory.list(), "");
+        inventory.insert(&world, rocks[0]).unwrap();
+        inventory.insert(&world, lighter).unwrap();
+        inventory.insert(&world, rocks[1]).unwrap();
+        assert_eq!(inventory.quantity(Item::Rock), 2);
+        assert_eq!(inventory.list(), "Rock x2, Lighter");
+        assert_eq!(inventory.items(), vec![rocks[0], rocks[1], lighter]);
+
+        assert!(inventory.remove(rocks[0]));
+        assert!(!inventory.remove(rocks[0]));
+        assert_eq!(inventory.list(), "Rock, Lighter");
+        inventory.remove(rocks[1]);
+        assert_eq!(inventory.list(), "Lighter");
+    }
+
+    #[test]
+    fn inventory_refuses_items_past_its_capacity() {
+        let (mut world, _) = setup_world();
+        let canister = spawn_item(&mut world, Item::Canister);
+        let rock = spawn_item(&mut world, Item::Rock);
+        let watches = [spawn_item(&mut world, Item::Watch), spawn_item(&mut world, Item::Watch)];
+
+        let mut light = InventoryComponent::with_capacity(Some(5), None);
+        light.insert(&world, canister).unwrap();
+        assert_eq!(light.weight(&world), 4);
+        assert_eq!(light.insert(&world, rock), Err(String::from("The rock is too heavy to carry with everything else")));
+        assert_eq!(light.list(), "Canister");
+
+        // More of something already held doesn't need another slot
+        let mut small = InventoryComponent::with_capacity(None, Some(1));
+        small.insert(&world, watches[0]).unwrap();
+        small.insert(&world, watches[1]).unwrap();
+        assert_eq!(small.insert(&world, rock), Err(String::from("There's no room left for the rock")));
+        assert_eq!(small.list(), "Watch x2");
+    }
+
+    #[test]
+    fn check_pocket_with_nothing_held() {
+        let (world, _) = setup_world();
+        run(&world, "drop lighter");
+        run(&world, "drop watch");
+        assert_eq!(run(&world, "check pocket"), "My pockets are empty");
+    }
+}
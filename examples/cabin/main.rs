@@ -0,0 +1,332 @@
+mod areas;
+mod conditions;
+mod dev;
+mod dialogue;
+mod doors;
+mod endings;
+mod items;
+mod map;
+mod npc;
+mod player;
+mod survival;
+
+use crate::areas::{AreaComponent, RoomComponent, get_place};
+use crate::dev::DevToolsPlugin;
+use crate::dialogue::DialoguePlugin;
+use crate::doors::DoorsPlugin;
+use crate::endings::{EndingCategory, EndingsPlugin, GameOutcome, render_ending};
+use crate::items::{InventoryComponent, ItemsPlugin};
+use crate::map::LocationComponent;
+use crate::npc::NpcPlugin;
+use crate::player::{Command, ExplorationPlugin, get_player};
+use crate::survival::{SurvivalPlugin, WarmthComponent};
+use ecs_small::{App, CommandHelp, Message, Plugin, Priority, RenderQueue, Session, Stage, State, TuiBackend, World};
+
+// Ugly will fix later
+const INTRO_STRING: &str = "I finally found my way out of the woods. I see the cabin in the distance.
+I am freezing though and don't know how much longer I can stay out here. 
+I'll keep an eye on my {{Watch}} to help me.";
+
+const MENU_STRING: &str = "> *Lost in the Snow*
+> {{1}} New Game
+> {{2}} Quit";
+
+const GAME_OVER_STRING: &str = "I could {{Restart}} and try again or {{Quit}}";
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum GameState {
+    Menu,
+    Playing,
+    Paused,
+    Dialogue,
+    GameOver,
+}
+
+// The entities the game's systems are built around, the door is only there once the doors plugin adds it
+struct GameEntities {
+    player: usize,
+    door: Option<usize>,
+}
+
+#[derive(Default)]
+struct GameResult {
+    outcome: Option<GameOutcome>,
+}
+
+fn set_state(world: &World, next: GameState) {
+    if let Some(mut state) = world.borrow_resource_mut::<State<GameState>>() {
+        state.set(next);
+    }
+}
+
+// The menu, pausing, quitting and the game over screen
+struct StatesPlugin;
+
+impl Plugin<GameState> for StatesPlugin {
+    fn build(&self, app: &mut App<GameState>) {
+        for state in [GameState::Menu, GameState::Playing, GameState::Paused, GameState::Dialogue, GameState::GameOver] {
+            app.add_system_to_stage(state, Stage::First, quit_system);
+        }
+        app.add_startup_system(|world| world.add_resource(GameResult::default()))
+            .add_enter_system(GameState::Menu, menu_enter_system)
+            .add_system(GameState::Menu, menu_system)
+            .add_system(GameState::Playing, playing_command_system)
+            .add_enter_system(GameState::Paused, paused_enter_system)
+            .add_system(GameState::Paused, paused_system)
+            .add_enter_system(GameState::GameOver, game_over_enter_system)
+            .add_system(GameState::GameOver, game_over_system)
+            .add_command("Pause", "I can {{Pause}} the game to take a break or {{Quit}} whenever I like")
+            .add_command("Quit", "");
+    }
+}
+
+// The whole of Lost in the Snow, the order matters since later plugins build on what earlier ones set up
+struct CabinGamePlugin;
+
+impl Plugin<GameState> for CabinGamePlugin {
+    fn build(&self, app: &mut App<GameState>) {
+        app.add_plugin(StatesPlugin)
+            .add_plugin(ItemsPlugin)
+            .add_plugin(ExplorationPlugin)
+            .add_plugin(DoorsPlugin)
+            .add_plugin(SurvivalPlugin)
+            .add_plugin(DialoguePlugin)
+            .add_plugin(NpcPlugin)
+            .add_plugin(EndingsPlugin);
+    }
+}
+
+fn quit_system(world: &World, input: &[&str], _: &mut String) {
+    if matches!(input.first().map(|command| Command::from_str(command)), Some(Ok(Command::Quit))) {
+        world.borrow_resource_mut::<Session>().unwrap().quit = true;
+    }
+}
+
+fn menu_enter_system(_: &World, _: &[&str], output: &mut String) {
+    output.push_str(MENU_STRING);
+}
+
+fn menu_system(world: &World, input: &[&str], output: &mut String) {
+    match input.first().map(|choice| choice.to_lowercase()).as_deref() {
+        Some("1" | "new") => set_state(world, GameState::Playing),
+        Some("2") => world.borrow_resource_mut::<Session>().unwrap().quit = true,
+        Some("quit" | "exit") => (), // The quit system takes care of it
+        _ => output.push_str(MENU_STRING),
+    }
+}
+
+// Commands about the game itself rather than anything in it
+fn playing_command_system(world: &World, input: &[&str], output: &mut String) {
+    match input.first().map(|command| Command::from_str(command)) {
+        Some(Ok(Command::Help)) => output.push_str(&world.borrow_resource::<CommandHelp>().unwrap().render()),
+        Some(Ok(Command::Pause)) => set_state(world, GameState::Paused),
+        Some(Ok(Command::Resume)) => output.push_str("I'm not taking a break right now"),
+        Some(Ok(Command::Restart)) => output.push_str("There's no starting over until this is done, one way or another"),
+        _ => (),
+    }
+}
+
+fn paused_enter_system(_: &World, _: &[&str], output: &mut String) {
+    output.push_str("> The game is paused, I can {{Resume}} whenever I'm ready");
+}
+
+fn paused_system(world: &World, input: &[&str], output: &mut String) {
+    match input.first().map(|command| Command::from_str(command)) {
+        Some(Ok(Command::Resume)) => set_state(world, GameState::Playing),
+        Some(Ok(Command::Quit)) => (),
+        _ => output.push_str("> The game is paused, I can {{Resume}} whenever I'm ready"),
+    }
+}
+
+fn game_over_enter_system(world: &World, _: &[&str], _: &mut String) {
+    let mut message = Message::narration(GAME_OVER_STRING);
+    if let Some(outcome) = world.borrow_resource::<GameResult>().unwrap().outcome.as_ref() {
+        message.text = format!("{}\n{}", render_ending(outcome), GAME_OVER_STRING);
+        // The time running out replaces whatever the player was doing
+        if outcome.category == EndingCategory::Death {
+            message.priority = Priority::Critical;
+        }
+    }
+    world.borrow_resource_mut::<RenderQueue>().unwrap().push(message);
+}
+
+fn game_over_system(world: &World, input: &[&str], output: &mut String) {
+    match input.first().map(|command| Command::from_str(command)) {
+        Some(Ok(Command::Restart)) => world.borrow_resource_mut::<Session>().unwrap().restart = true,
+        Some(Ok(Command::Quit)) => (),
+        _ => output.push_str(GAME_OVER_STRING),
+    }
+}
+
+// Where the player is, how long until they freeze and what they're carrying, for the TUI's status bar
+fn status_bar(world: &World) -> String {
+    let player_entity = get_player(world);
+    let place = match get_place(world, player_entity) {
+        Some(place) => place,
+        None => return String::new(),
+    };
+    let location = match place.room {
+        Some(room) => world.borrow_component::<RoomComponent>().unwrap()[room].as_ref().map_or(String::new(), |room| room.name.clone()),
+        None => {
+            let area = world.borrow_component::<AreaComponent>().unwrap()[place.area].as_ref().map_or(String::new(), |area| area.name.clone());
+            let locations = world.borrow_component::<LocationComponent>().unwrap();
+            locations[player_entity].map_or(area.clone(), |location| format!("{} {}", area, location))
+        }
+    };
+    let time = world.borrow_component::<WarmthComponent>().unwrap()[player_entity]
+        .as_ref()
+        .map_or(String::from("-"), |warmth| match warmth.remaining_time() {
+            Some(seconds_left) => format!("{}s until I freeze", seconds_left),
+            None => String::from("out of the wind"),
+        });
+    let held = world.borrow_component::<InventoryComponent>().unwrap()[player_entity]
+        .as_ref()
+        .map_or(String::new(), |inventory| inventory.list());
+    format!(" {} | {} | {}", location, time, held)
+}
+
+fn main() {
+    let mut app = App::new(GameState::Menu);
+    app.add_plugin(CabinGamePlugin);
+    // The developer console is only there when asked for, cargo run --example cabin -- --dev
+    if std::env::args().any(|arg| arg == "--dev") {
+        app.add_plugin(DevToolsPlugin);
+    }
+    // The full screen UI needs a terminal to draw on and a player typing at it, otherwise it's the usual line by line output
+    let is_terminal = std::io::IsTerminal::is_terminal(&std::io::stdout()) && std::io::IsTerminal::is_terminal(&std::io::stdin());
+    if std::env::args().any(|arg| arg == "--tui") && is_terminal {
+        app.set_backend(TuiBackend::new(status_bar));
+    }
+    app.run();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::areas::update_room_system;
+    use crate::items::{ChargesComponent, Item, NameComponent, find_item, get_item_kind};
+    use crate::player::update_player_system;
+    use crate::survival::{GameClock, time_system};
+    use std::time::Duration;
+    use ecs_small::{ScriptedBackend, Value};
+
+    // A new game with the player standing on the driveway holding a lighter and a watch
+    pub(crate) fn setup_world() -> (World, usize) {
+        let mut app = App::new(GameState::Menu);
+        app.add_plugin(CabinGamePlugin);
+        app.reset();
+        let player_entity = get_player(&app.world);
+        (app.world, player_entity)
+    }
+
+    pub(crate) fn run(world: &World, command: &str) -> String {
+        let mut output = String::new();
+        update_player_system(world, &command.split_ascii_whitespace().collect::<Vec<_>>(), &mut output);
+        output
+    }
+
+    pub(crate) fn held_kinds(world: &World, entity: usize) -> Vec<Item> {
+        let inventories = world.borrow_component::<InventoryComponent>().unwrap();
+        inventories[entity].as_ref().unwrap().items().iter().filter_map(|item| get_item_kind(world, *item)).collect()
+    }
+
+    // Winds the game clock back so the next tick counts that many seconds
+    pub(crate) fn pass_time(world: &World, seconds: u64) {
+        world.borrow_resource_mut::<GameClock>().unwrap().last_tick -= Duration::from_secs(seconds);
+        time_system(world);
+    }
+
+    pub(crate) fn warmth_of(world: &World, entity: usize) -> f64 {
+        world.borrow_component::<WarmthComponent>().unwrap()[entity].as_ref().unwrap().warmth
+    }
+
+    pub(crate) fn held(world: &World, entity: usize, kind: Item) -> usize {
+        let inventories = world.borrow_component::<InventoryComponent>().unwrap();
+        find_item(world, inventories[entity].as_ref().unwrap().items().as_slice(), kind).unwrap()
+    }
+
+    pub(crate) fn charges_of(world: &World, item_entity: usize) -> u32 {
+        world.borrow_component::<ChargesComponent>().unwrap()[item_entity].as_ref().unwrap().charges
+    }
+
+    // Starts a new game, with or without --dev, and plays through the input
+    pub(crate) fn play(dev: bool, input: &[String]) -> (World, Vec<String>) {
+        let mut app = App::new(GameState::Menu);
+        app.add_plugin(CabinGamePlugin);
+        if dev {
+            app.add_plugin(DevToolsPlugin);
+        }
+        let backend = ScriptedBackend::new(std::iter::once(String::from("1")).chain(input.iter().cloned()));
+        let shown = backend.shown();
+        app.set_backend(backend);
+        app.run();
+        let shown = shown.borrow().clone();
+        (app.world, shown)
+    }
+
+    pub(crate) fn npc_named(world: &World, name: &str) -> usize {
+        let names = world.borrow_component::<NameComponent>().unwrap();
+        names.iter().position(|other| other.as_ref().is_some_and(|other| other.name == name)).unwrap()
+    }
+
+    #[test]
+    fn components_can_be_edited_by_path() {
+        let (world, player_entity) = setup_world();
+        let door_entity = world.borrow_resource::<GameEntities>().unwrap().door.unwrap();
+        assert_eq!(world.get_path(door_entity, "door.is_frozen"), Ok(Value::Bool(true)));
+        world.set_path(player_entity, "location.x", Value::Int(2)).unwrap();
+        world.set_path(player_entity, "location.y", Value::Int(2)).unwrap();
+        world.set_path(door_entity, "door.is_frozen", Value::parse("false")).unwrap();
+        update_room_system(&world, &["go", "door"], &mut String::new());
+        let area = get_place(&world, player_entity).unwrap().area;
+        assert_eq!(world.borrow_component::<AreaComponent>().unwrap()[area].as_ref().unwrap().name, "cabin");
+    }
+
+    #[test]
+    fn game_states_move_between_menu_playing_and_paused() {
+        let mut app = App::new(GameState::Menu);
+        app.add_plugin(CabinGamePlugin);
+        app.reset();
+        let (states, world) = (&app.schedule, &app.world);
+        let state = |world: &World| world.borrow_resource::<State<GameState>>().unwrap().get();
+        let output = |world: &World| world.borrow_resource_mut::<RenderQueue>().unwrap().compose();
+        states.start(world);
+        assert_eq!(output(world), MENU_STRING);
+        states.run(world, &["load"]);
+        assert_eq!(output(world), MENU_STRING);
+
+        states.run(world, &["1"]);
+        assert_eq!(state(world), GameState::Playing);
+        assert!(output(world).starts_with(INTRO_STRING));
+
+        states.run(world, &["pause"]);
+        assert_eq!(state(world), GameState::Paused);
+        // Nothing but resuming or quitting does anything while paused
+        states.run(world, &["move", "forward"]);
+        let player_entity = world.borrow_resource::<GameEntities>().unwrap().player;
+        assert_eq!(world.borrow_component::<LocationComponent>().unwrap()[player_entity], Some(LocationComponent { x: 0, y: 0 }));
+        states.run(world, &["resume"]);
+        assert_eq!(state(world), GameState::Playing);
+
+        // Each plugin adds its own commands to the help
+        output(world);
+        states.run(world, &["help"]);
+        assert!(output(world).starts_with("> Available Commands {{Pause, Quit, Move, Check, Use, Go, Drop, Examine, Give, Map, Open, Close, Unlock, Look, Talk}}"));
+
+        states.run(world, &["quit"]);
+        assert!(world.borrow_resource::<Session>().unwrap().quit);
+    }
+
+    #[test]
+    fn the_title_menu_can_quit() {
+        let mut app = App::new(GameState::Menu);
+        app.add_plugin(CabinGamePlugin);
+        let backend = ScriptedBackend::new(["2", "1"]);
+        let shown = backend.shown();
+        app.set_backend(backend);
+        app.run();
+        // The game never starts
+        assert_eq!(*shown.borrow(), vec![MENU_STRING]);
+        assert!(app.world.borrow_resource::<Session>().unwrap().quit);
+    }
+}
@@ -0,0 +1,785 @@
+use crate::areas::{ExitsComponent, PlaceComponent, get_place};
+use crate::conditions::Condition;
+use crate::doors::DoorComponent;
+use crate::items::Item;
+use crate::npc::Behavior;
+use crate::player::{Command, get_player};
+use ecs_small::{Component, Reflect, World};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Direction {
+    Forward,
+    Back,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub fn from_str(s: &str) -> Result<Direction, &str> {
+        match s.to_lowercase().as_str() {
+            "forward" => Ok(Direction::Forward),
+            "back" => Ok(Direction::Back),
+            "left" => Ok(Direction::Left),
+            "right" => Ok(Direction::Right),
+            _ => Err("Failed to find direction"),
+        }
+    }
+
+    pub const ALL: [Direction; 4] = [Direction::Forward, Direction::Back, Direction::Left, Direction::Right];
+
+    // Forward heads towards the cabin which is the positive y direction
+    fn offset(&self) -> (i32, i32) {
+        match self {
+            Direction::Forward => (0, 1),
+            Direction::Back => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Component, Reflect)]
+#[component(name = "location")]
+pub struct LocationComponent {
+    pub x: i32,
+    pub y: i32,
+ }
+
+impl LocationComponent {
+    // The neighbouring location, whether or not the map actually has a tile there
+    pub fn step(&self, dir: Direction) -> LocationComponent {
+        let (dx, dy) = dir.offset();
+        LocationComponent {
+            x: self.x + dx,
+            y: self.y + dy,
+        }
+    }
+
+    // How many steps apart two locations are when walking the grid
+    pub fn distance(&self, other: &LocationComponent) -> i32 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    // The map decides if the move is allowed, on failure the location is left untouched
+    // and the current tile's reason for not moving is returned
+    pub fn update_location(&mut self, dir: Direction, map: &MapComponent) -> Result<(), String> {
+        *self = map.check_move(self, dir)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for LocationComponent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "x: {}, y:{}", self.x, self.y)
+    }
+}
+
+// Returned when a map file doesn't follow the expected format
+// line and column both start counting at 1, a line of 0 means the file itself couldn't be read
+#[derive(Debug, PartialEq)]
+pub struct MapParseError {
+    pub line: usize,
+    pub column: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for MapParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}", self.reason);
+        }
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.reason)
+    }
+}
+
+// A single value between the '|' separators of a map line
+#[derive(Debug, PartialEq)]
+pub enum MapValue {
+    Location(LocationComponent), // {x:0,y:0}
+    Text(String), // "Quoted text, supports \" \\ \n and \t escapes"
+    Word(String), // Bare words such as item names
+    Property(String, Box<MapValue>), // name=value, for example wall=forward
+}
+
+#[derive(Debug)]
+pub struct MapField {
+    pub value: MapValue,
+    pub column: usize,
+}
+
+// Walks a single line of a map file, keeping track of the column so errors can point at the problem
+pub struct MapLineScanner {
+    chars: Vec<char>,
+    pos: usize,
+    pub line: usize,
+}
+
+impl MapLineScanner {
+    pub fn new(line: usize, text: &str) -> Self {
+        MapLineScanner {
+            chars: text.chars().collect(),
+            pos: 0,
+            line,
+        }
+    }
+
+    pub fn error(&self, reason: impl Into<String>) -> MapParseError {
+        self.error_at(self.pos + 1, reason)
+    }
+
+    pub fn error_at(&self, column: usize, reason: impl Into<String>) -> MapParseError {
+        MapParseError {
+            line: self.line,
+            column,
+            reason: reason.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    // Anything after a '#' that isn't inside of quotes is a comment
+    fn at_line_end(&self) -> bool {
+        matches!(self.peek(), None | Some('#'))
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), MapParseError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("expected '{}' but found '{}'", expected, c))),
+            None => Err(self.error(format!("expected '{}' but the line ended", expected))),
+        }
+    }
+
+    fn parse_integer(&mut self) -> Result<i32, MapParseError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits
+            .parse()
+            .map_err(|_| self.error_at(start + 1, format!("expected a whole number but found \"{}\"", digits)))
+    }
+
+    // Reads "x:<number>" where the axis name is supplied by the caller
+    fn parse_axis(&mut self, axis: char) -> Result<i32, MapParseError> {
+        self.skip_whitespace();
+        self.expect(axis)?;
+        self.skip_whitespace();
+        self.expect(':')?;
+        self.skip_whitespace();
+        self.parse_integer()
+    }
+
+    fn parse_location(&mut self) -> Result<LocationComponent, MapParseError> {
+        self.expect('{')?;
+        let x = self.parse_axis('x')?;
+        self.skip_whitespace();
+        self.expect(',')?;
+        let y = self.parse_axis('y')?;
+        self.skip_whitespace();
+        self.expect('}')?;
+        Ok(LocationComponent { x, y })
+    }
+
+    fn parse_quoted(&mut self) -> Result<String, MapParseError> {
+        let start = self.pos;
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(text),
+                Some('\\') => match self.bump() {
+                    Some('"') => text.push('"'),
+                    Some('\\') => text.push('\\'),
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some(c) => return Err(self.error_at(self.pos, format!("unknown escape sequence '\\{}'", c))),
+                    None => return Err(self.error("escape sequence cut off by the end of the line")),
+                },
+                Some(c) => text.push(c),
+                None => return Err(self.error_at(start + 1, "text is missing its closing quote")),
+            }
+        }
+    }
+
+    fn parse_word(&mut self) -> Result<String, MapParseError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error("expected a value"));
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn parse_value(&mut self) -> Result<MapValue, MapParseError> {
+        match self.peek() {
+            Some('{') => Ok(MapValue::Location(self.parse_location()?)),
+            Some('"') => Ok(MapValue::Text(self.parse_quoted()?)),
+            Some(c) if c.is_alphanumeric() => {
+                let word = self.parse_word()?;
+                self.skip_whitespace();
+                if self.peek() != Some('=') {
+                    return Ok(MapValue::Word(word));
+                }
+                self.pos += 1;
+                self.skip_whitespace();
+                let value = self.parse_value()?;
+                Ok(MapValue::Property(word, Box::new(value)))
+            }
+            Some(c) => Err(self.error(format!("unexpected '{}'", c))),
+            None => Err(self.error("expected a value but the line ended")),
+        }
+    }
+
+    // Splits the line into its '|' separated fields, blank and comment lines have no fields
+    pub fn parse_fields(&mut self) -> Result<Vec<MapField>, MapParseError> {
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.at_line_end() {
+            return Ok(fields);
+        }
+        loop {
+            self.skip_whitespace();
+            let column = self.pos + 1;
+            let value = self.parse_value()?;
+            fields.push(MapField { value, column });
+            self.skip_whitespace();
+            if self.at_line_end() {
+                return Ok(fields);
+            }
+            self.expect('|')?;
+        }
+    }
+}
+
+// Used whenever a tile doesn't supply its own blocked message
+pub const DEFAULT_BLOCKED_MESSAGE: &str = "I don't want to stray to far from my house";
+
+// Out on the map the wind takes one point of warmth a second unless the tile says otherwise
+pub const DEFAULT_TILE_CHILL: u32 = 1;
+
+// Everything the map knows about a single location
+pub struct Tile {
+    description: String,
+    // Directions the player can't leave this tile in, even if there is a tile on the other side
+    walls: HashSet<Direction>,
+    // What the player says when they can't leave this tile in the direction they asked for
+    blocked: Option<String>,
+    // Warmth lost every second while standing here
+    pub chill: u32,
+}
+
+impl Tile {
+    fn get_blocked_message(&self) -> &str {
+        self.blocked.as_deref().unwrap_or(DEFAULT_BLOCKED_MESSAGE)
+    }
+}
+
+#[derive(Component)]
+#[component(name = "map")]
+// The tiles of a grid area by location, kept on the area's entity
+pub struct MapComponent {
+    pub area: HashMap<LocationComponent, Tile>,
+}
+
+impl MapComponent {
+    fn empty() -> Self {
+        MapComponent {
+            area: HashMap::new(),
+        }
+    }
+
+    // Adds the tile described by an already split map line, each line is made of '|' separated fields
+    //     {x:0,y:1}|"Theres a small pile of rubble here"|rock|wall=left|blocked="Only woods that way"|chill=2
+    // The location and description are required, the item, walls, blocked message and chill are optional
+    // Returns the item lying on the tile, if there is one
+    fn insert_tile(&mut self, scanner: &MapLineScanner, fields: Vec<MapField>) -> Result<Option<Item>, MapParseError> {
+        let mut fields = fields.into_iter();
+        let first = match fields.next() {
+            Some(field) => field,
+            None => return Err(scanner.error("expected a tile")),
+        };
+        let location = match first.value {
+            MapValue::Location(location) => location,
+            _ => return Err(scanner.error_at(first.column, "a tile must start with its location, like {x:0,y:0}")),
+        };
+        if self.area.contains_key(&location) {
+            return Err(scanner.error_at(first.column, format!("tile {{{}}} is defined more than once", location)));
+        }
+        let mut tile = match fields.next() {
+            Some(MapField { value: MapValue::Text(description), .. }) => Tile {
+                description,
+                walls: HashSet::new(),
+                blocked: None,
+                chill: DEFAULT_TILE_CHILL,
+            },
+            Some(field) => return Err(scanner.error_at(field.column, "the description must be quoted text")),
+            None => return Err(scanner.error(format!("tile {{{}}} is missing a description", location))),
+        };
+        let mut item = None;
+        for field in fields {
+            match field.value {
+                MapValue::Word(_) if item.is_some() => {
+                    return Err(scanner.error_at(field.column, "a tile can only hold one item"));
+                }
+                MapValue::Word(word) => match Item::from_str(&word) {
+                    Ok(found) => item = Some(found),
+                    Err(_) => return Err(scanner.error_at(field.column, format!("\"{}\" is not an item", word))),
+                },
+                MapValue::Property(name, value) => match (name.as_str(), *value) {
+                    ("wall", MapValue::Word(dir)) => match Direction::from_str(&dir) {
+                        Ok(dir) => {
+                            tile.walls.insert(dir);
+                        }
+                        Err(_) => return Err(scanner.error_at(field.column, format!("\"{}\" is not a direction", dir))),
+                    },
+                    ("blocked", MapValue::Text(message)) => tile.blocked = Some(message),
+                    ("chill", MapValue::Word(chill)) => {
+                        tile.chill = chill.parse().map_err(|_| scanner.error_at(field.column, "chill must be a whole number"))?;
+                    }
+                    ("chill", _) => return Err(scanner.error_at(field.column, "chill must be a whole number")),
+                    ("wall", _) => return Err(scanner.error_at(field.column, "a wall needs a direction, like wall=forward")),
+                    ("blocked", _) => return Err(scanner.error_at(field.column, "the blocked message must be quoted text")),
+                    _ => return Err(scanner.error_at(field.column, format!("unknown tile property \"{}\"", name))),
+                },
+                _ => return Err(scanner.error_at(field.column, "expected an item name or a tile property")),
+            }
+        }
+        self.area.insert(location, tile);
+        Ok(item)
+    }
+
+    // May need to return Result and not Option, still mulling over if I want an Err message
+    pub fn check_area(&self, location: &LocationComponent) -> Result<&String, &str> {
+        if let Some(tile) = self.area.get(location) {
+            return Ok(&tile.description);
+        } 
+        Err("Player is out of bounds")
+    }
+    // A move is only legal when the destination is on the map and the current tile has no wall that way
+    fn check_move(&self, from: &LocationComponent, dir: Direction) -> Result<LocationComponent, String> {
+        let tile = match self.area.get(from) {
+            Some(tile) => tile,
+            None => return Err(String::from(DEFAULT_BLOCKED_MESSAGE)),
+        };
+        let destination = from.step(dir);
+        if tile.walls.contains(&dir) || !self.area.contains_key(&destination) {
+            return Err(String::from(tile.get_blocked_message()));
+        }
+        Ok(destination)
+    }
+}
+
+// Every map tile an entity has stood on along with its area, the minimap only shows these
+#[derive(Component, Default)]
+#[component(name = "explored")]
+pub struct ExploredComponent {
+    tiles: HashSet<(usize, LocationComponent)>,
+}
+
+impl ExploredComponent {
+    fn visit(&mut self, area: usize, location: LocationComponent) {
+        self.tiles.insert((area, location));
+    }
+
+    fn has_visited(&self, area: usize, location: &LocationComponent) -> bool {
+        self.tiles.contains(&(area, *location))
+    }
+}
+
+// Exits are kept as written until every area is loaded, since they can lead into areas defined later
+pub struct ExitDefinition {
+    pub line: usize,
+    pub from: MapField,
+    pub keyword: String,
+    pub to: MapField,
+    pub condition: Option<Condition>,
+    pub blocked: Option<String>,
+}
+
+pub struct NpcDefinition {
+    pub line: usize,
+    pub location: MapField,
+    pub name: String,
+    pub description: String,
+    pub behavior: Behavior,
+    pub carries: Vec<Item>,
+}
+
+pub struct ContainerDefinition {
+    pub line: usize,
+    pub place: MapField,
+    pub name: String,
+    pub is_open: bool,
+    pub key_item: Option<Item>,
+    pub contents: Vec<Item>,
+}
+
+// Everything declared in a single area file
+pub struct AreaDefinition {
+    pub map: MapComponent,
+    // Items written on a tile, in the order they appear in the file
+    pub items: Vec<(LocationComponent, Item)>,
+    pub rooms: Vec<RoomDefinition>,
+    pub exits: Vec<ExitDefinition>,
+    pub containers: Vec<ContainerDefinition>,
+    pub npcs: Vec<NpcDefinition>,
+}
+
+pub struct RoomDefinition {
+    pub name: String,
+    pub description: String,
+    pub chill: u32,
+}
+
+impl AreaDefinition {
+    // Area files mix three kinds of lines, rooms and exits start with a keyword
+    //     {x:2,y:2}|"A tile, written the same way as any map"
+    //     room|hall|"The hallway of my cabin"|chill=0
+    //     exit|<from>|<keyword>|<to>|when="<condition>"|blocked="<message>"
+    //     container|<place>|"<name>"|closed|locked=<key item>|contains=<item>
+    //     npc|<tile>|"<name>"|"<description>"|<behavior>|waypoint=<tile>|carries=<item>
+    // <from> is a room name or a tile in this area, exits off of a tile belong to a doorway entity on that tile
+    // <to> is also a room or a tile, places in other areas are written as basement={x:0,y:0} or cabin=hall
+    pub fn parse(contents: &str) -> Result<Self, MapParseError> {
+        let mut definition = AreaDefinition {
+            map: MapComponent::empty(),
+            items: Vec::new(),
+            rooms: Vec::new(),
+            exits: Vec::new(),
+            containers: Vec::new(),
+            npcs: Vec::new(),
+        };
+        for (index, line) in contents.lines().enumerate() {
+            let mut scanner = MapLineScanner::new(index + 1, line);
+            let fields = scanner.parse_fields()?;
+            match fields.first().map(|field| &field.value) {
+                None => continue,
+                Some(&MapValue::Location(location)) => {
+                    if let Some(item) = definition.map.insert_tile(&scanner, fields)? {
+                        definition.items.push((location, item));
+                    }
+                }
+                Some(MapValue::Word(kind)) if kind == "room" => definition.parse_room(&scanner, fields)?,
+                Some(MapValue::Word(kind)) if kind == "exit" => definition.parse_exit(&scanner, fields)?,
+                Some(MapValue::Word(kind)) if kind == "container" => definition.parse_container(&scanner, fields)?,
+                Some(MapValue::Word(kind)) if kind == "npc" => definition.parse_npc(&scanner, fields)?,
+                Some(_) => return Err(scanner.error_at(fields[0].column, "expected a tile, room, exit, container or npc")),
+            }
+        }
+        Ok(definition)
+    }
+
+    fn parse_room(&mut self, scanner: &MapLineScanner, fields: Vec<MapField>) -> Result<(), MapParseError> {
+        let mut room = match &fields[1..] {
+            [MapField { value: MapValue::Word(name), .. }, MapField { value: MapValue::Text(description), .. }, ..] => RoomDefinition {
+                name: name.clone(),
+                description: description.clone(),
+                chill: 0,
+            },
+            _ => return Err(scanner.error_at(fields[0].column, "a room is written as room|<name>|\"<description>\"")),
+        };
+        for field in &fields[3..] {
+            match &field.value {
+                MapValue::Property(name, value) if name == "chill" => match value.as_ref() {
+                    MapValue::Word(chill) => {
+                        room.chill = chill.parse().map_err(|_| scanner.error_at(field.column, "chill must be a whole number"))?;
+                    }
+                    _ => return Err(scanner.error_at(field.column, "chill must be a whole number")),
+                },
+                _ => return Err(scanner.error_at(field.column, "expected chill=<number>")),
+            }
+        }
+        if self.rooms.iter().any(|existing| existing.name == room.name) {
+            return Err(scanner.error_at(fields[1].column, format!("room \"{}\" is already defined", room.name)));
+        }
+        self.rooms.push(room);
+        Ok(())
+    }
+
+    fn parse_exit(&mut self, scanner: &MapLineScanner, fields: Vec<MapField>) -> Result<(), MapParseError> {
+        if fields.len() < 4 {
+            return Err(scanner.error("an exit is written as exit|<from>|<keyword>|<to>"));
+        }
+        let mut fields = fields.into_iter().skip(1);
+        let from = fields.next().unwrap();
+        let keyword = fields.next().unwrap();
+        let keyword = match keyword.value {
+            MapValue::Word(keyword) | MapValue::Text(keyword) => keyword.to_lowercase(),
+            _ => return Err(scanner.error_at(keyword.column, "expected the name of the exit")),
+        };
+        let to = fields.next().unwrap();
+        let mut exit = ExitDefinition {
+            line: scanner.line,
+            from,
+            keyword,
+            to,
+            condition: None,
+            blocked: None,
+        };
+        for field in fields {
+            match field.value {
+                MapValue::Property(name, value) if name == "when" => match *value {
+                    MapValue::Text(when) => {
+                        // The condition starts after `when="`
+                        let offset = field.column + name.len() + 1;
+                        exit.condition = Some(Condition::parse(&when).map_err(|err| scanner.error_at(offset + err.column, err.reason))?);
+                    }
+                    _ => return Err(scanner.error_at(field.column, "the condition must be quoted text")),
+                },
+                MapValue::Property(name, value) if name == "blocked" => match *value {
+                    MapValue::Text(message) => exit.blocked = Some(message),
+                    _ => return Err(scanner.error_at(field.column, "the blocked message must be quoted text")),
+                },
+                _ => return Err(scanner.error_at(field.column, "expected when=\"<condition>\" or blocked=\"<message>\"")),
+            }
+        }
+        self.exits.push(exit);
+        Ok(())
+    }
+
+    // Containers start open unless they're marked closed, a locked container is always closed
+    fn parse_container(&mut self, scanner: &MapLineScanner, fields: Vec<MapField>) -> Result<(), MapParseError> {
+        if fields.len() < 3 {
+            return Err(scanner.error("a container is written as container|<place>|\"<name>\""));
+        }
+        let mut fields = fields.into_iter().skip(1);
+        let place = fields.next().unwrap();
+        let name = fields.next().unwrap();
+        let name = match name.value {
+            MapValue::Word(name) | MapValue::Text(name) => name.to_lowercase(),
+            _ => return Err(scanner.error_at(name.column, "expected the name of the container")),
+        };
+        let mut container = ContainerDefinition {
+            line: scanner.line,
+            place,
+            name,
+            is_open: true,
+            key_item: None,
+            contents: Vec::new(),
+        };
+        for field in fields {
+            let item = match &field.value {
+                MapValue::Word(state) if state == "closed" => {
+                    container.is_open = false;
+                    continue;
+                }
+                MapValue::Property(name, value) if name == "locked" || name == "contains" => match value.as_ref() {
+                    MapValue::Word(item) => {
+                        Item::from_str(item).map_err(|_| scanner.error_at(field.column, format!("\"{}\" is not an item", item)))?
+                    }
+                    _ => return Err(scanner.error_at(field.column, "expected the name of an item")),
+                },
+                _ => return Err(scanner.error_at(field.column, "expected closed, locked=<key item> or contains=<item>")),
+            };
+            match &field.value {
+                MapValue::Property(name, _) if name == "locked" => {
+                    container.key_item = Some(item);
+                    container.is_open = false;
+                }
+                _ => container.contents.push(item),
+            }
+        }
+        self.containers.push(container);
+        Ok(())
+    }
+
+    // Patrolling NPCs list their waypoints in the order they walk them
+    fn parse_npc(&mut self, scanner: &MapLineScanner, fields: Vec<MapField>) -> Result<(), MapParseError> {
+        let usage = "an npc is written as npc|<tile>|\"<name>\"|\"<description>\"|<behavior>";
+        if fields.len() < 5 {
+            return Err(scanner.error(usage));
+        }
+        let mut fields = fields.into_iter().skip(1);
+        let location = fields.next().unwrap();
+        if !matches!(location.value, MapValue::Location(_)) {
+            return Err(scanner.error_at(location.column, "an npc has to start on a tile"));
+        }
+        // Names keep their case, people like Walt go by their name while the dog is "the dog"
+        let (name, description) = match (fields.next().unwrap(), fields.next().unwrap()) {
+            (
+                MapField { value: MapValue::Word(name) | MapValue::Text(name), .. },
+                MapField { value: MapValue::Text(description), .. },
+            ) => (name, description),
+            (_, field) => return Err(scanner.error_at(field.column, usage)),
+        };
+        let behavior = fields.next().unwrap();
+        let mut behavior = match &behavior.value {
+            MapValue::Word(word) => Behavior::from_str(word)
+                .map_err(|_| scanner.error_at(behavior.column, format!("\"{}\" is not a behavior, try idle, wander, patrol, follow or flee", word)))?,
+            _ => return Err(scanner.error_at(behavior.column, "expected idle, wander, patrol, follow or flee")),
+        };
+        let mut carries = Vec::new();
+        for field in fields {
+            match (&mut behavior, &field.value) {
+                (_, MapValue::Property(name, value)) if name == "carries" => match value.as_ref() {
+                    MapValue::Word(item) => {
+                        carries.push(Item::from_str(item).map_err(|_| scanner.error_at(field.column, format!("\"{}\" is not an item", item)))?)
+                    }
+                    _ => return Err(scanner.error_at(field.column, "expected the name of an item")),
+                },
+                (Behavior::Patrol { path, .. }, MapValue::Property(name, value)) if name == "waypoint" => match value.as_ref() {
+                    MapValue::Location(waypoint) => path.push(*waypoint),
+                    _ => return Err(scanner.error_at(field.column, "a waypoint is a tile like {x:0,y:0}")),
+                },
+                (Behavior::Patrol { .. }, _) => return Err(scanner.error_at(field.column, "expected waypoint=<tile>")),
+                _ => return Err(scanner.error_at(field.column, "expected carries=<item>, only patrolling npcs take waypoints")),
+            }
+        }
+        if matches!(&behavior, Behavior::Patrol { path, .. } if path.is_empty()) {
+            return Err(scanner.error("a patrolling npc needs at least one waypoint"));
+        }
+        self.npcs.push(NpcDefinition {
+            line: scanner.line,
+            location,
+            name,
+            description,
+            behavior,
+            carries,
+        });
+        Ok(())
+    }
+}
+
+// Marks the tile the entity is standing on as explored, rooms aren't on the map so they don't count
+pub fn explore(world: &World, entity: usize) {
+    let place = match get_place(world, entity) {
+        Some(place) if place.room.is_none() => place,
+        _ => return,
+    };
+    let location = match world.borrow_component::<LocationComponent>().unwrap()[entity] {
+        Some(location) => location,
+        None => return,
+    };
+    if let Some(explored) = world.borrow_component_mut::<ExploredComponent>().unwrap()[entity].as_mut() {
+        explored.visit(place.area, location);
+    }
+}
+
+// Draws the entity's area from above with forward at the top
+// Only the tiles they've stood on are filled in, the doors can be seen from anywhere
+fn render_minimap(world: &World, entity: usize) -> String {
+    let place = match get_place(world, entity) {
+        Some(place) if place.room.is_none() => place,
+        _ => return String::from("I know my way around in here, I don't need a map"),
+    };
+    let maps = world.borrow_component::<MapComponent>().unwrap();
+    let map = match maps[place.area].as_ref() {
+        Some(map) if !map.area.is_empty() => map,
+        _ => return String::from("I have no idea where I am"),
+    };
+    let locations = world.borrow_component::<LocationComponent>().unwrap();
+    let explored = world.borrow_component::<ExploredComponent>().unwrap();
+    let visited = |location: &LocationComponent| explored[entity].as_ref().is_some_and(|explored| explored.has_visited(place.area, location));
+    let on_this_map = |other: usize| get_place(world, other) == Some(PlaceComponent { area: place.area, room: None });
+    let doors: Vec<LocationComponent> = world.query::<DoorComponent>().into_iter().filter(|door| on_this_map(*door)).filter_map(|door| locations[door]).collect();
+    let exits: Vec<LocationComponent> = world.query::<ExitsComponent>().into_iter().filter(|exit| on_this_map(*exit)).filter_map(|exit| locations[exit]).collect();
+
+    let xs = map.area.keys().map(|location| location.x);
+    let ys = map.area.keys().map(|location| location.y);
+    let (min_x, max_x) = (xs.clone().min().unwrap(), xs.max().unwrap());
+    let (min_y, max_y) = (ys.clone().min().unwrap(), ys.max().unwrap());
+    let border = format!("+{}+", "-".repeat(((max_x - min_x + 1) * 2 + 1) as usize));
+    let mut lines = vec![border.clone()];
+    for y in (min_y..=max_y).rev() {
+        let cells: Vec<&str> = (min_x..=max_x)
+            .map(|x| {
+                let location = LocationComponent { x, y };
+                if locations[entity] == Some(location) {
+                    "@"
+                } else if doors.contains(&location) {
+                    "D"
+                } else if !map.area.contains_key(&location) || !visited(&location) {
+                    " "
+                } else if exits.contains(&location) {
+                    "E"
+                } else {
+                    "."
+                }
+            })
+            .collect();
+        lines.push(format!("| {} |", cells.join(" ")));
+    }
+    lines.push(border);
+    lines.push(String::from("@ me, D door, E a way in or out, . somewhere I've been"));
+    lines.join("\n")
+}
+
+pub fn map_system(world: &World, input: &[&str], output: &mut String) {
+    if let Some(Ok(Command::Map)) = input.first().map(|command| Command::from_str(command)) {
+        output.push_str(&render_minimap(world, get_player(world)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{run, setup_world};
+
+    fn map_error(contents: &str) -> String {
+        AreaDefinition::parse(contents).err().expect("the map should have been rejected").to_string()
+    }
+
+    #[test]
+    fn map_errors_point_at_the_line_and_column() {
+        assert_eq!(map_error("{x:0,y:0}|\"The driveway"), "line 1, column 11: text is missing its closing quote");
+        assert_eq!(map_error("\n{x:0,y}|\"Woods\""), "line 2, column 7: expected ':' but found '}'");
+        assert_eq!(map_error("{x:zero,y:0}|\"Woods\""), "line 1, column 4: expected a whole number but found \"\"");
+        assert_eq!(map_error("{x:0,y:0}|\"Bad \\q\""), "line 1, column 17: unknown escape sequence '\\q'");
+        assert_eq!(map_error("{x:0,y:0}|\"Woods\"|rock|key"), "line 1, column 24: a tile can only hold one item");
+        assert_eq!(
+            map_error("{x:0,y:0}|\"Woods\"\n# The same tile again\n{x:0,y:0}|\"More woods\""),
+            "line 3, column 1: tile {x: 0, y:0} is defined more than once"
+        );
+    }
+
+    #[test]
+    fn map_text_has_escapes_and_skips_comments_and_blank_lines() {
+        let map = AreaDefinition::parse("# The whole map\n\n   \n{x:0,y:0}|\"A sign: \\\"Keep \\\\ out\\\"\\n\\tNo really\" # The sign").unwrap().map;
+        assert_eq!(map.area.len(), 1);
+        assert_eq!(map.area[&LocationComponent { x: 0, y: 0 }].description, "A sign: \"Keep \\ out\"\n\tNo really");
+    }
+
+    #[test]
+    fn minimap_only_shows_where_ive_been() {
+        let (world, player_entity) = setup_world();
+        run(&world, "move forward");
+        run(&world, "move right");
+        assert_eq!(
+            render_minimap(&world, player_entity),
+            "+-------+\n\
+             |     D |\n\
+             | . @   |\n\
+             | .     |\n\
+             +-------+\n\
+             @ me, D door, E a way in or out, . somewhere I've been"
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use crate::GameState;
+use crate::areas::{PlaceComponent, get_place};
+use crate::doors::OpenableComponent;
+use crate::items::{InventoryComponent, NameComponent};
+use crate::map::{Direction, LocationComponent, MapComponent};
+use crate::player::get_player;
+use ecs_small::{App, Bundle, Component, Plugin, World};
+use std::time::SystemTime;
+
+// Someone named at the same place as the entity who could be handed something, containers aren't handed things
+pub fn find_named_entity(world: &World, name: &str, entity: usize) -> Option<usize> {
+    let names = world.borrow_component::<NameComponent>()?;
+    let inventories = world.borrow_component::<InventoryComponent>()?;
+    let openables = world.borrow_component::<OpenableComponent>();
+    let locations = world.borrow_component::<LocationComponent>().unwrap();
+    let place = get_place(world, entity)?;
+    names.iter().enumerate().find_map(|(other, other_name)| {
+        let other_name = other_name.as_ref()?;
+        let is_here = get_place(world, other) == Some(place) && (place.room.is_some() || locations[other] == locations[entity]);
+        let is_container = openables.as_ref().is_some_and(|openables| openables.get(other).is_some());
+        (other != entity && is_here && !is_container && inventories[other].is_some() && other_name.name.eq_ignore_ascii_case(name))
+            .then_some(other)
+    })
+}
+
+// How close I can get before a skittish animal runs off
+const FLEE_DISTANCE: i32 = 2;
+
+// How an NPC decides where to step each turn
+pub enum Behavior {
+    // Stays where they are
+    Idle,
+    Wander,
+    // Walks to each waypoint in turn, going back to the first after the last
+    Patrol { path: Vec<LocationComponent>, next: usize },
+    Follow,
+    // Keeps its distance once the player comes within FLEE_DISTANCE
+    Flee,
+}
+
+impl Behavior {
+    pub fn from_str(s: &str) -> Result<Behavior, &str> {
+        match s {
+            "idle" => Ok(Behavior::Idle),
+            "wander" => Ok(Behavior::Wander),
+            "patrol" => Ok(Behavior::Patrol { path: Vec::new(), next: 0 }),
+            "follow" => Ok(Behavior::Follow),
+            "flee" => Ok(Behavior::Flee),
+            _ => Err("Failed to find the behavior"),
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct NpcBundle {
+    pub name: NameComponent,
+    pub inventory: InventoryComponent,
+    pub behavior: BehaviorComponent,
+    pub location: LocationComponent,
+    pub place: PlaceComponent,
+}
+
+// Anyone else out here, they move around the map by the same rules as the player
+#[derive(Component)]
+#[component(name = "behavior")]
+pub struct BehaviorComponent {
+    pub behavior: Behavior,
+    // What I see when they're on the same tile as me
+    pub description: String,
+}
+
+// A small xorshift generator, seeded once so a playthrough can be repeated with the same seed
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on zero
+        Random { state: seed.max(1) }
+    }
+
+    fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos() as u64);
+        Random::new(nanos)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    // A number from 0 up to but not including the bound
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+// Directions sorted by how close a step that way gets to the target, only steps that get closer are kept
+fn directions_towards(from: LocationComponent, target: LocationComponent) -> Vec<Direction> {
+    let mut directions: Vec<Direction> = Direction::ALL
+        .into_iter()
+        .filter(|dir| from.step(*dir).distance(&target) < from.distance(&target))
+        .collect();
+    directions.sort_by_key(|dir| from.step(*dir).distance(&target));
+    directions
+}
+
+fn directions_away(from: LocationComponent, target: LocationComponent) -> Vec<Direction> {
+    Direction::ALL
+        .into_iter()
+        .filter(|dir| from.step(*dir).distance(&target) > from.distance(&target))
+        .collect()
+}
+
+// Where the NPC would like to step, in order of preference, the map still decides which of them are possible
+fn choose_directions(behavior: &mut Behavior, location: LocationComponent, player: Option<LocationComponent>, random: &mut Random) -> Vec<Direction> {
+    match behavior {
+        Behavior::Idle => Vec::new(),
+        Behavior::Wander => {
+            // Half the time it stays put
+            if random.below(2) == 0 {
+                return Vec::new();
+            }
+            let mut directions = Direction::ALL.to_vec();
+            let first = random.below(directions.len());
+            directions.rotate_left(first);
+            directions
+        }
+        Behavior::Patrol { path, next } => {
+            if path.is_empty() {
+                return Vec::new();
+            }
+            if path[*next] == location {
+                *next = (*next + 1) % path.len();
+            }
+            directions_towards(location, path[*next])
+        }
+        Behavior::Follow => player.map_or(Vec::new(), |player| directions_towards(location, player)),
+        Behavior::Flee => match player {
+            Some(player) if location.distance(&player) <= FLEE_DISTANCE => directions_away(location, player),
+            _ => Vec::new(),
+        },
+    }
+}
+
+// The descriptions of the NPCs standing at a place, NPCs only ever walk about on a map
+pub fn describe_npcs_at(world: &World, place: PlaceComponent, location: LocationComponent) -> Vec<String> {
+    let behaviors = match world.borrow_component::<BehaviorComponent>() {
+        Some(behaviors) => behaviors,
+        None => return Vec::new(),
+    };
+    let locations = world.borrow_component::<LocationComponent>().unwrap();
+    behaviors
+        .iter()
+        .enumerate()
+        .filter(|(entity, _)| place.room.is_none() && get_place(world, *entity) == Some(place) && locations[*entity] == Some(location))
+        .filter_map(|(_, behavior)| behavior.as_ref().map(|behavior| behavior.description.clone()))
+        .collect()
+}
+
+// Every NPC takes a step according to its behavior, the player only notices the ones that come up to them
+fn ai_system(world: &World, _: &[&str], game_output: &mut String) {
+    let player_entity = get_player(world);
+    let npcs = world.query::<BehaviorComponent>();
+    let player_place = get_place(world, player_entity);
+    let mut random = world.borrow_resource_mut::<Random>().unwrap();
+    let maps = world.borrow_component::<MapComponent>().unwrap();
+    let names = world.borrow_component::<NameComponent>().unwrap();
+    let mut behaviors = world.borrow_component_mut::<BehaviorComponent>().unwrap();
+    let mut locations = world.borrow_component_mut::<LocationComponent>().unwrap();
+    let player_location = locations[player_entity];
+
+    for npc in npcs {
+        let place = match get_place(world, npc) {
+            Some(place) if place.room.is_none() => place,
+            _ => continue,
+        };
+        let (map, mut location) = match (maps[place.area].as_ref(), locations[npc]) {
+            (Some(map), Some(location)) => (map, location),
+            _ => continue,
+        };
+        // The player is only noticed by NPCs walking about the same map
+        let player = player_location.filter(|_| player_place == Some(place));
+        let was_with_player = player == Some(location);
+        let behavior = behaviors[npc].as_mut().unwrap();
+        for dir in choose_directions(&mut behavior.behavior, location, player, &mut random) {
+            if location.update_location(dir, map).is_ok() {
+                break;
+            }
+        }
+        locations[npc] = Some(location);
+        if !was_with_player && player == Some(location) {
+            if !game_output.is_empty() {
+                game_output.push('\n');
+            }
+            let name = names[npc].as_ref().map_or("something", |name| name.name.as_str());
+            game_output.push_str(&format!("The {} comes up to me", name));
+        }
+    }
+}
+
+// Gives the NPCs loaded with the areas something to do each turn
+pub struct NpcPlugin;
+
+impl Plugin<GameState> for NpcPlugin {
+    fn build(&self, app: &mut App<GameState>) {
+        app.add_startup_system(|world| world.add_resource(Random::from_time()))
+            .add_system(GameState::Playing, ai_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{npc_named, setup_world};
+
+    #[test]
+    fn npcs_step_by_their_behavior() {
+        let (mut world, player_entity) = setup_world();
+        world.add_resource(Random::new(7));
+        let location_of = |world: &World, entity: usize| world.borrow_component::<LocationComponent>().unwrap()[entity].unwrap();
+        let (dog, fox) = (npc_named(&world, "dog"), npc_named(&world, "fox"));
+
+        // The fox starts close enough to run off, the dog a diagonal step away so it takes two turns to reach me
+        assert_eq!(location_of(&world, fox).distance(&location_of(&world, player_entity)), FLEE_DISTANCE);
+        let mut output = String::new();
+        ai_system(&world, &[], &mut output);
+        assert_eq!(location_of(&world, dog).distance(&location_of(&world, player_entity)), 1);
+        ai_system(&world, &[], &mut output);
+        assert_eq!(location_of(&world, dog), location_of(&world, player_entity));
+        assert!(output.contains("The dog comes up to me"));
+        assert!(location_of(&world, fox).distance(&location_of(&world, player_entity)) > FLEE_DISTANCE);
+    }
+
+    #[test]
+    fn patrols_loop_through_their_waypoints() {
+        let mut random = Random::new(1);
+        let waypoints = vec![LocationComponent { x: 1, y: 0 }, LocationComponent { x: 1, y: 2 }];
+        let mut behavior = Behavior::Patrol { path: waypoints, next: 0 };
+        let mut location = LocationComponent { x: 1, y: 0 };
+        let mut visited = Vec::new();
+        for _ in 0..4 {
+            let dir = choose_directions(&mut behavior, location, None, &mut random)[0];
+            location = location.step(dir);
+            visited.push(location);
+        }
+        assert_eq!(visited, vec![
+            LocationComponent { x: 1, y: 1 },
+            LocationComponent { x: 1, y: 2 },
+            LocationComponent { x: 1, y: 1 },
+            LocationComponent { x: 1, y: 0 },
+        ]);
+    }
+}
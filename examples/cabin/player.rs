@@ -0,0 +1,409 @@
+use crate::{GameEntities, GameState, INTRO_STRING};
+use crate::areas::{AreaComponent, ExitsComponent, PlaceComponent, RoomComponent, describe_room, get_place, load_areas, update_room_system};
+use crate::doors::{DoorComponent, LockableComponent, OpenableComponent, containers_at};
+use crate::items::{InventoryComponent, Item, NameComponent, POCKET_MAX_WEIGHT, describe_item, drop_item, find_item, get_item_kind, give_item, items_at, refill_item, spawn_item, use_item_charge};
+use crate::map::{Direction, ExploredComponent, LocationComponent, MapComponent, explore, map_system};
+use crate::npc::{BehaviorComponent, describe_npcs_at, find_named_entity};
+use crate::survival::{LIGHTER_WARMTH, WarmthComponent};
+use ecs_small::{App, Bundle, Component, Message, Plugin, Reflect, RenderQueue, World};
+
+#[derive(PartialEq, Debug)]
+pub enum Command {
+    Move, // Only takes one of the four directions forward/back/left/right
+    Check, // Doesn't need to take anything else
+    Use,  // Needs to list the various items in the Game
+    Go, // Takes the name of an exit such as door or "up the stairs"
+    Drop, // Takes an item to leave where I'm standing
+    Examine, // Takes an item either held or lying nearby
+    Give, // Takes an item, "to" and the name of who gets it
+    Open, // Takes the name of a container such as the trash can
+    Close, // Takes the name of a container
+    Unlock, // Takes the name of a container, the key has to be in my pocket
+    Look, // Takes "in" and the name of a container
+    Talk, // Takes "to" and the name of someone nearby
+    Map, // Doesn't need to take anything else
+    Help,
+    Pause,
+    Resume,
+    Restart,
+    Quit,
+}
+
+impl Command {
+    pub fn from_str(s: &str) -> Result<Command, &str> {
+        // Prepping the value before it's used
+        match s.to_lowercase().as_str() {
+            "move" => Ok(Command::Move),
+            "check" => Ok(Command::Check),
+            "use" => Ok(Command::Use),
+            "go" => Ok(Command::Go),
+            "drop" => Ok(Command::Drop),
+            "examine" => Ok(Command::Examine),
+            "give" => Ok(Command::Give),
+            "open" => Ok(Command::Open),
+            "close" => Ok(Command::Close),
+            "unlock" => Ok(Command::Unlock),
+            "look" => Ok(Command::Look),
+            "talk" => Ok(Command::Talk),
+            "map" => Ok(Command::Map),
+            "help" => Ok(Command::Help),
+            "pause" => Ok(Command::Pause),
+            "resume" => Ok(Command::Resume),
+            "restart" => Ok(Command::Restart),
+            "quit" | "exit" => Ok(Command::Quit),
+            _ => Err(s),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Inquire {
+    Pocket,
+    Area
+}
+
+impl Inquire {
+    fn from_str(s: &str) -> Result<Inquire, &str> {
+        match s {
+            "pocket" => Ok(Inquire::Pocket),
+            "area" => Ok(Inquire::Area),
+            _ => Err("Failed to find something to inquire")
+        }
+    }
+}
+
+#[derive(Component, Reflect)]
+#[component(name = "player")]
+pub struct PlayerComponent {
+    pub is_alive: bool,
+}
+
+impl PlayerComponent {
+    fn new() -> Self {
+        PlayerComponent {
+            is_alive: true,
+        }
+    }
+
+
+    pub fn set_is_alive(&mut self, is_alive: bool) {
+        self.is_alive = is_alive;
+    }
+}
+
+// Runs once when leaving the title menu for a new game
+fn print_introduction_system(_: &World, _: &[&str], output: &mut String) {
+    output.push_str(INTRO_STRING);
+}
+
+// The entity the person playing controls
+pub fn get_player(world: &World) -> usize {
+    world.borrow_resource::<GameEntities>().expect("The game hasn't been set up").player
+}
+
+pub fn update_player_system(world: & World, command_vec: &[&str], game_output: &mut String) {
+    let player_entity = get_player(world);
+    if command_vec.is_empty() {
+        game_output.push_str("I may need some {{help}} with what to do next");
+        return;
+    }
+
+    // Im not fully grasping the ECS system yet since Im editing on the player variables based on input
+    // Perhaps if I add other entities into this world I will better understand how to break out the logic
+    let player_place = get_place(world, player_entity).expect("Player is not in the world");
+    let player_room = player_place.room;
+    // Copied out so the other systems can borrow the inventories while the command runs
+    let held = world.borrow_component::<InventoryComponent>().unwrap()[player_entity]
+        .as_ref()
+        .map(|inventory| inventory.items())
+        .unwrap_or_default();
+    // Copied out so the location storage is free to look up other entities, it's written back after a move
+    let mut player_location = world.borrow_component::<LocationComponent>().unwrap()[player_entity].expect("Player does not have a location");
+    let at_frozen_door = world.borrow_component::<DoorComponent>().is_some_and(|doors| {
+        let locations = world.borrow_component::<LocationComponent>().unwrap();
+        doors.iter().any(|(entity, door)| {
+            door.is_frozen() && locations[entity] == Some(player_location) && get_place(world, entity) == Some(player_place)
+        })
+    });
+    let map = world.borrow_component::<MapComponent>().unwrap();
+
+    let player_map = map[player_place.area].as_ref().expect("Player's area does not have a map");
+
+    let mut iter = command_vec.iter();
+    let command = Command::from_str(iter.next().unwrap_or(&"Command Required to act {{Move, Check, Use}}"));
+    match command {
+        Ok(Command::Move) if player_room.is_some() => {
+            game_output.push_str("There's nowhere to walk to in here, I should {{Go}} somewhere instead");
+        }
+        Ok(Command::Go) => (), // Exits are handled by the room system
+        Ok(Command::Open | Command::Close | Command::Unlock | Command::Look) => (), // Handled by the container system
+        Ok(Command::Talk) => (), // Handled by the dialogue system
+        Ok(Command::Map) => (), // Handled by the map system
+        Ok(Command::Help | Command::Pause | Command::Resume | Command::Restart | Command::Quit) => (), // Handled by the game states
+        Ok(Command::Move) => {
+            if let Ok(dir) = Direction::from_str(iter.next().unwrap_or(&"Failed to find next entry in vector")) {
+                // If the location hasn't changed don't change the map data
+                if let Err(blocked) = player_location.update_location(dir, player_map) {
+                    game_output.push_str(&blocked);
+                    return;
+                }
+                world.borrow_component_mut::<LocationComponent>().unwrap()[player_entity] = Some(player_location);
+                explore(world, player_entity);
+                if let Ok(result) = player_map.check_area(&player_location) {
+                    game_output.push_str(result);
+                }
+                for npc in describe_npcs_at(world, player_place, player_location) {
+                    game_output.push('\n');
+                    game_output.push_str(&npc);
+                }
+                
+            } else {
+                // TODO - Make this more immersive "I'm not sure which direction to go"
+                game_output.push_str("Failed to find a direction to move to {{Forward, Back, Left, Right}}");
+            }
+        },
+        Ok(Command::Check) => {
+            if let Ok(inq) = Inquire::from_str(iter.next().unwrap_or(&"Failed to find next entry in the vector")) {
+                match inq {
+                    Inquire::Area => {
+                        let found = items_at(world, player_place, player_location);
+                        let containers = containers_at(world, player_place, player_location);
+                        let mut lines = Vec::new();
+                        if let Some(room_entity) = player_room {
+                            lines.push(describe_room(world, room_entity));
+                        }
+                        lines.extend(describe_npcs_at(world, player_place, player_location));
+                        if lines.is_empty() && found.is_empty() && containers.is_empty() {
+                            game_output.push_str("Looks like there's nothing of interest here");
+                            return;
+                        }
+                        // Picked up items leave the map and go with the player
+                        let mut places = world.borrow_component_mut::<PlaceComponent>().unwrap();
+                        let mut locations = world.borrow_component_mut::<LocationComponent>().unwrap();
+                        let mut inventories = world.borrow_component_mut::<InventoryComponent>().unwrap();
+                        let player_inventory = inventories[player_entity].as_mut().expect("Player can't carry anything");
+                        for item_entity in found {
+                            let name = get_item_kind(world, item_entity).map_or("something", |kind| kind.to_string());
+                            // Anything too heavy stays where it is
+                            match player_inventory.insert(world, item_entity) {
+                                Ok(()) => {
+                                    places[item_entity] = None;
+                                    locations[item_entity] = None;
+                                    lines.push(format!("Looks like there's {} here. I'll hold on to it for later", name));
+                                }
+                                Err(reason) => lines.push(format!("Looks like there's {} here. {}", name, reason)),
+                            }
+                        }
+                        let names = world.borrow_component::<NameComponent>().unwrap();
+                        for container_entity in containers {
+                            if let Some(name) = names[container_entity].as_ref() {
+                                lines.push(format!("There's a {{{{{}}}}} here, I could {{{{Open}}}} it or {{{{Look}}}} in it", name.name));
+                            }
+                        }
+                        game_output.push_str(&lines.join("\n"));
+                    }
+                    Inquire::Pocket => {
+                        let inventories = world.borrow_component::<InventoryComponent>().unwrap();
+                        match inventories[player_entity].as_ref() {
+                            Some(inventory) if !inventory.is_empty() => {
+                                game_output.push_str(format!("I have {{{{{}}}}} in my pocket", inventory.list()).as_str());
+                            }
+                            _ => game_output.push_str("My pockets are empty"),
+                        }
+                    } 
+                }
+            }  else {
+                game_output.push_str("I'm not sure what to check, all I see is the {{Area}} and all I have are what's in my {{Pocket}}");
+            }
+        }
+        Ok(Command::Use) => {
+            if let Ok(item) = Item::from_str(iter.next().unwrap_or(&"Failed to find next entry in the vector")) {
+                let item_entity = match find_item(world, &held, item) {
+                    Some(item_entity) => item_entity,
+                    None => {
+                        game_output.push_str("I don't have that on me right now");
+                        return;
+                    }
+                };
+                // use <item> on <other item>
+                if iter.next() == Some(&"on") {
+                    let target = iter.next().and_then(|target| Item::from_str(target).ok());
+                    match target.and_then(|target| find_item(world, &held, target)) {
+                        Some(target_entity) => match refill_item(world, target_entity, item_entity) {
+                            Ok(message) | Err(message) => game_output.push_str(&message),
+                        },
+                        None => game_output.push_str("I don't have anything like that to use it on"),
+                    }
+                    return;
+                }
+                match item {
+                    // At the door the door system's answer takes the place of this one
+                    Item::Canister => {
+                        let message = Message::narration("I have no use for this canister right now").from(player_entity);
+                        world.borrow_resource_mut::<RenderQueue>().unwrap().push(message);
+                    }
+                    Item::Lighter => {
+                        // At the frozen door the door system decides what the flame is used for, it shouldn't burn fuel twice
+                        if at_frozen_door {
+                            return;
+                        }
+                        if let Err(message) = use_item_charge(world, item_entity) {
+                            game_output.push_str(&message);
+                            return;
+                        }
+                        let mut warmths = world.borrow_component_mut::<WarmthComponent>().unwrap();
+                        if let Some(warmth) = warmths[player_entity].as_mut() {
+                            warmth.warm_up(LIGHTER_WARMTH);
+                        }
+                        game_output.push_str("I cup my hands around the lighter's flame, it helps a little");
+                    }
+                    Item::Key => {
+                        game_output.push_str("There must be something I can {{Unlock}} with this key");
+                    }
+                    Item::Watch => {
+                        let warmths = world.borrow_component::<WarmthComponent>().unwrap();
+                        if let Some(warmth) = warmths[player_entity].as_ref() {
+                            game_output.push_str(&warmth.get_body_state());
+                        }
+                    }
+                    Item::Rock => {
+                        let message = Message::narration("I have no use for this rock right now").from(player_entity);
+                        world.borrow_resource_mut::<RenderQueue>().unwrap().push(message);
+                    }
+                }
+            } else {
+                game_output.push_str("Not sure what I should use. Perhaps I should {{check pocket}}");
+            }
+        }
+        Ok(Command::Drop) => {
+            let item_entity = iter.next().and_then(|item| Item::from_str(item).ok()).and_then(|item| find_item(world, &held, item));
+            match item_entity {
+                Some(item_entity) => {
+                    drop_item(world, player_entity, item_entity);
+                    let name = get_item_kind(world, item_entity).map_or("it", |kind| kind.to_string());
+                    game_output.push_str(&format!("I leave the {} here, I can always come back for it", name.to_lowercase()));
+                }
+                None => game_output.push_str("I don't have that on me right now, maybe I should {{Check Pocket}}"),
+            }
+        }
+        Ok(Command::Examine) => {
+            // Held items are checked first, then whatever is lying around
+            let item = iter.next().and_then(|item| Item::from_str(item).ok());
+            let item_entity = item.and_then(|item| {
+                find_item(world, &held, item).or_else(|| find_item(world, &items_at(world, player_place, player_location), item))
+            });
+            match item_entity {
+                Some(item_entity) => game_output.push_str(&describe_item(world, item_entity)),
+                None => game_output.push_str("I don't see that anywhere around here"),
+            }
+        }
+        Ok(Command::Give) => {
+            // give <item> to <name>
+            let item_entity = iter.next().and_then(|item| Item::from_str(item).ok()).and_then(|item| find_item(world, &held, item));
+            let receiver = match (iter.next(), iter.next()) {
+                (Some(&"to"), Some(name)) => find_named_entity(world, name, player_entity).ok_or(*name),
+                _ => {
+                    game_output.push_str("I need to say who I'm giving it to, {{Give}} <item> to <name>");
+                    return;
+                }
+            };
+            match (item_entity, receiver) {
+                (None, _) => game_output.push_str("I don't have that on me right now, maybe I should {{Check Pocket}}"),
+                (_, Err(name)) => game_output.push_str(&format!("There's no one called {} here", name)),
+                (Some(item_entity), Ok(receiver)) => {
+                    if let Err(reason) = give_item(world, player_entity, receiver, item_entity) {
+                        game_output.push_str(&reason);
+                        return;
+                    }
+                    let item = get_item_kind(world, item_entity).map_or("it", |kind| kind.to_string());
+                    let names = world.borrow_component::<NameComponent>().unwrap();
+                    let name = names[receiver].as_ref().map_or("them", |name| name.name.as_str());
+                    // Animals and such are "the dog", people go by their name
+                    let name = if name.starts_with(char::is_uppercase) { name.to_string() } else { format!("the {}", name) };
+                    game_output.push_str(&format!("I hand the {} over to {}", item.to_lowercase(), name));
+                }
+            }
+        }
+        Err(e) => {
+            // TODO - Make this more immersive "I'm not sure which direction to go"
+            let mut queue = world.borrow_resource_mut::<RenderQueue>().unwrap();
+            queue.push(Message::system(format!("Error bad input: \"{}\" is not a command", e)));
+            queue.push(Message::hint("Try asking for {{Help}}"));
+        }
+    }
+}
+
+// The player and the areas they walk around in, items are spawned while the areas load so this goes after the items
+// The map files also place containers and NPCs, so their components are registered here too
+pub struct ExplorationPlugin;
+
+impl Plugin<GameState> for ExplorationPlugin {
+    fn build(&self, app: &mut App<GameState>) {
+        app.register_reflect::<PlayerComponent>()
+            .register_reflect::<NameComponent>()
+            .register_component::<InventoryComponent>()
+            .register_reflect::<LocationComponent>()
+            .register_debug::<PlaceComponent>()
+            .register_component::<AreaComponent>()
+            .register_component::<MapComponent>()
+            .register_component::<RoomComponent>()
+            .register_component::<ExitsComponent>()
+            .register_reflect::<OpenableComponent>()
+            .register_reflect::<LockableComponent>()
+            .register_component::<BehaviorComponent>()
+            .register_component::<ExploredComponent>()
+            .add_startup_system(spawn_player_system)
+            .add_exit_system(GameState::Menu, print_introduction_system)
+            .add_system(GameState::Playing, update_player_system)
+            .add_system(GameState::Playing, update_room_system)
+            .add_system(GameState::Playing, map_system)
+            .add_command("Move", "When I Move I need to decide on a Direction {{Forward, Back, Left, Right}}")
+            .add_command("Check", "I could Check my {{Pocket}} or the surrounding {{Area}} ")
+            .add_command("Use", "I can also {{Use}} items in my inventory, {{Drop}} them or {{Give}} them to someone")
+            .add_command("Go", "If I find a door or some stairs I can {{Go}} through them")
+            .add_command("Drop", "")
+            .add_command("Examine", "To get a closer look at an item I can {{Examine}} it")
+            .add_command("Give", "")
+            .add_command("Map", "I can picture a {{Map}} of everywhere I've been");
+    }
+}
+
+#[derive(Bundle)]
+struct PlayerBundle {
+    player: PlayerComponent,
+    name: NameComponent,
+    inventory: InventoryComponent,
+    location: LocationComponent,
+}
+
+fn spawn_player_system(world: &mut World) {
+    let lighter = spawn_item(world, Item::Lighter);
+    let watch = spawn_item(world, Item::Watch);
+    let mut inventory = InventoryComponent::with_capacity(Some(POCKET_MAX_WEIGHT), None);
+    for item_entity in [lighter, watch] {
+        inventory.insert(world, item_entity).expect("The starting items don't fit in the pockets");
+    }
+    let player_entity = world.spawn(PlayerBundle {
+        player: PlayerComponent::new(),
+        name: NameComponent { name: String::from("Jakob") },
+        inventory,
+        location: LocationComponent{x: 0, y: 0},
+    });
+
+    // Every area has its own map file, exits between them are declared in the files themselves
+    let areas = match load_areas(world, &[
+        ("outside", include_str!("player_map.txt")),
+        ("cabin", include_str!("cabin_map.txt")),
+        ("basement", include_str!("basement_map.txt")),
+    ]) {
+        Ok(areas) => areas,
+        Err(err) => {
+            println!("Failed to load the {}", err);
+            std::process::exit(1);
+        }
+    };
+    world.add_component_to_entity(player_entity, PlaceComponent { area: areas["outside"], room: None });
+    world.add_component_to_entity(player_entity, ExploredComponent::default());
+    explore(world, player_entity);
+    world.add_resource(GameEntities { player: player_entity, door: None });
+}
@@ -0,0 +1,444 @@
+use crate::GameState;
+use crate::areas::{RoomComponent, get_place};
+use crate::conditions::Condition;
+use crate::doors::DoorComponent;
+use crate::map::{DEFAULT_TILE_CHILL, LocationComponent, MapComponent, MapField, MapLineScanner, MapParseError, MapValue};
+use crate::player::{PlayerComponent, get_player};
+use ecs_small::{App, Component, Message, Plugin, Reflect, RenderQueue, Stage, World};
+use std::time::{Duration, SystemTime};
+
+// How close an entity is to freezing, they freeze once warmth reaches zero
+#[derive(Component, Reflect)]
+#[component(name = "warmth")]
+pub struct WarmthComponent {
+    pub warmth: f64,
+    pub max_warmth: f64,
+    // Warmth lost per second where the entity currently is, kept up to date by the time system
+    pub chill: u32,
+}
+
+impl WarmthComponent {
+    fn new(max_warmth: f64) -> Self {
+        WarmthComponent {
+            warmth: max_warmth,
+            max_warmth,
+            chill: 0,
+        }
+    }
+
+    pub fn warm_up(&mut self, amount: f64) {
+        self.warmth = (self.warmth + amount).min(self.max_warmth);
+    }
+
+    fn cool_down(&mut self, amount: f64) {
+        self.warmth = (self.warmth - amount).max(0.0);
+    }
+
+    fn is_frozen(&self) -> bool {
+        self.warmth <= 0.0
+    }
+
+    // What the watch tells the player about how they're holding up
+    pub fn get_body_state(&self) -> String {
+        let feeling = match self.warmth / self.max_warmth {
+            ratio if ratio > 0.75 => "I'm cold but I'm holding up",
+            ratio if ratio > 0.4 => "I can't stop shivering",
+            ratio if ratio > 0.15 => "My hands are numb and I can barely hold my watch",
+            _ => "I can hardly feel anything anymore",
+        };
+        match self.remaining_time() {
+            Some(seconds_left) => format!("{}. At this rate I have about {} seconds before I freeze", feeling, seconds_left),
+            None => format!("{}. At least I'm out of the wind here", feeling),
+        }
+    }
+
+    // Seconds until freezing where the entity is now, None when they aren't losing any warmth
+    pub fn remaining_time(&self) -> Option<u64> {
+        if self.chill == 0 {
+            return None;
+        }
+        Some((self.warmth / self.chill as f64).ceil() as u64)
+    }
+}
+
+// Game time only moves forward while the clock is ticked, rather than following the wall clock directly
+pub struct GameClock {
+    pub last_tick: SystemTime,
+    elapsed: Duration,
+}
+
+impl GameClock {
+    fn new() -> Self {
+        GameClock {
+            last_tick: SystemTime::now(),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    // Returns how much game time passed since the last tick
+    fn tick(&mut self) -> Duration {
+        let now = SystemTime::now();
+        let delta = now.duration_since(self.last_tick).unwrap_or(Duration::ZERO);
+        self.elapsed += delta;
+        self.last_tick = now;
+        delta
+    }
+
+    // Picks the clock back up without counting the time it was stopped for
+    pub fn resume(&mut self) {
+        self.last_tick = SystemTime::now();
+    }
+
+    fn elapsed_secs(&self) -> u64 {
+        self.elapsed.as_secs()
+    }
+}
+
+// Things that happen to the world, queued by systems and the scheduler and applied by the event system
+#[derive(Debug, PartialEq, Clone)]
+pub enum GameEvent {
+    Say(String),
+    GasolineEvaporated,
+}
+
+#[derive(Default)]
+struct Events {
+    queue: Vec<GameEvent>,
+}
+
+impl Events {
+    fn push(&mut self, event: GameEvent) {
+        self.queue.push(event);
+    }
+
+    fn drain(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.queue)
+    }
+}
+
+#[derive(Clone)]
+struct ScheduledEvent {
+    // Seconds of game time
+    at: u64,
+    repeat_every: Option<u64>,
+    // Checked for the player when the event is due, the event is skipped if it doesn't hold
+    condition: Option<Condition>,
+    event: GameEvent,
+}
+
+// Holds events until the game clock reaches them, then hands them over to Events
+#[derive(Default)]
+pub struct Scheduler {
+    now: u64,
+    pending: Vec<ScheduledEvent>,
+}
+
+impl Scheduler {
+    fn schedule_at(&mut self, at: u64, event: GameEvent) {
+        self.schedule(ScheduledEvent { at, repeat_every: None, condition: None, event });
+    }
+
+    pub fn schedule_in(&mut self, delay: u64, event: GameEvent) {
+        self.schedule_at(self.now + delay, event);
+    }
+
+    fn schedule(&mut self, scheduled: ScheduledEvent) {
+        self.pending.push(scheduled);
+    }
+
+    // Removes every event due by `now` in the order they were due, repeating events are put back
+    // for their next time after `now`, so a long pause only fires them once
+    fn take_due(&mut self, now: u64) -> Vec<ScheduledEvent> {
+        self.now = now;
+        let (mut due, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending).into_iter().partition(|scheduled| scheduled.at <= now);
+        self.pending = pending;
+        due.sort_by_key(|scheduled| scheduled.at);
+        for scheduled in due.iter() {
+            if let Some(interval) = scheduled.repeat_every {
+                let mut next = scheduled.clone();
+                next.at += ((now - scheduled.at) / interval + 1) * interval;
+                self.pending.push(next);
+            }
+        }
+        due
+    }
+
+    // Runs every condition once so mistakes in events.txt are caught before the game starts
+    fn validate(&self, world: &World, entity: usize) -> Result<(), String> {
+        for scheduled in self.pending.iter() {
+            if let Some(condition) = &scheduled.condition {
+                condition.evaluate_bool(world, entity)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Events are written one per line as
+//     event|at=<seconds>|say="<text>"                          fires once that far into the game
+//     event|every=<seconds>|event=gasoline_evaporated           fires over and over
+// when="<condition>" can be added to only fire while the condition holds for the player
+fn load_events(scheduler: &mut Scheduler, contents: &str) -> Result<(), MapParseError> {
+    for (index, line) in contents.lines().enumerate() {
+        let mut scanner = MapLineScanner::new(index + 1, line);
+        let fields = scanner.parse_fields()?;
+        match fields.first() {
+            None => continue,
+            Some(MapField { value: MapValue::Word(kind), .. }) if kind == "event" => (),
+            Some(field) => return Err(scanner.error_at(field.column, "an event is written as event|at=<seconds>|say=\"<text>\"")),
+        }
+        let mut at = None;
+        let mut every = None;
+        let mut condition = None;
+        let mut event = None;
+        for field in &fields[1..] {
+            let (property, value) = match &field.value {
+                MapValue::Property(property, value) => (property.as_str(), value.as_ref()),
+                _ => return Err(scanner.error_at(field.column, "expected a property like at=60")),
+            };
+            match (property, value) {
+                ("at" | "every", MapValue::Word(word)) => {
+                    let seconds = word
+                        .parse::<u64>()
+                        .map_err(|_| scanner.error_at(field.column, format!("{} must be a whole number of seconds", property)))?;
+                    if property == "at" {
+                        at = Some(seconds);
+                    } else if seconds == 0 {
+                        return Err(scanner.error_at(field.column, "every must be at least 1 second"));
+                    } else {
+                        every = Some(seconds);
+                    }
+                }
+                ("when", MapValue::Text(when)) => {
+                    let offset = field.column + property.len() + 1;
+                    condition = Some(Condition::parse(when).map_err(|err| scanner.error_at(offset + err.column, err.reason))?);
+                }
+                ("say", MapValue::Text(text)) => event = Some(GameEvent::Say(text.clone())),
+                ("event", MapValue::Word(word)) if word == "gasoline_evaporated" => event = Some(GameEvent::GasolineEvaporated),
+                ("event", MapValue::Word(word)) => return Err(scanner.error_at(field.column, format!("\"{}\" is not an event", word))),
+                _ => return Err(scanner.error_at(field.column, format!("unexpected event property \"{}\"", property))),
+            }
+        }
+        let event = event.ok_or_else(|| scanner.error("an event needs something to happen, like say=\"<text>\""))?;
+        let at = match (at, every) {
+            (Some(at), _) => at,
+            (None, Some(every)) => every,
+            (None, None) => return Err(scanner.error("an event needs at=<seconds> or every=<seconds>")),
+        };
+        scheduler.schedule(ScheduledEvent { at, repeat_every: every, condition, event });
+    }
+    Ok(())
+}
+
+const MAX_WARMTH: f64 = 100.0;
+
+pub const LIGHTER_WARMTH: f64 = 10.0;
+
+// Warmth lost per second wherever the entity is, rooms and tiles each set their own
+pub fn get_chill(world: &World, entity: usize) -> u32 {
+    let place = match get_place(world, entity) {
+        Some(place) => place,
+        None => return 0,
+    };
+    if let Some(room) = place.room {
+        let rooms = world.borrow_component::<RoomComponent>().unwrap();
+        return rooms[room].as_ref().map_or(0, |room| room.chill);
+    }
+    let locations = world.borrow_component::<LocationComponent>().unwrap();
+    let maps = world.borrow_component::<MapComponent>().unwrap();
+    match (maps[place.area].as_ref(), locations[entity].as_ref()) {
+        (Some(map), Some(location)) => map.area.get(location).map_or(DEFAULT_TILE_CHILL, |tile| tile.chill),
+        _ => DEFAULT_TILE_CHILL,
+    }
+}
+
+// Drains warmth based on how much game time has passed since the last tick
+pub fn time_system(world: &World) {
+    let delta = world.borrow_resource_mut::<GameClock>().unwrap().tick();
+    for entity in world.query::<WarmthComponent>() {
+        let chill = get_chill(world, entity);
+        let mut warmths = world.borrow_component_mut::<WarmthComponent>().unwrap();
+        let warmth = warmths[entity].as_mut().unwrap();
+        warmth.chill = chill;
+        warmth.cool_down(delta.as_secs_f64() * chill as f64);
+        if warmth.is_frozen() {
+            if let Some(player_self) = world.borrow_component_mut::<PlayerComponent>().unwrap()[entity].as_mut() {
+                player_self.set_is_alive(false);
+            }
+        }
+    }
+}
+
+// Moves anything the scheduler has due at the current game time into the event queue
+fn scheduler_system(world: &World, player_entity: usize) {
+    let now = world.borrow_resource::<GameClock>().unwrap().elapsed_secs();
+    let due = world.borrow_resource_mut::<Scheduler>().unwrap().take_due(now);
+    let mut events = world.borrow_resource_mut::<Events>().unwrap();
+    for scheduled in due {
+        if let Some(condition) = &scheduled.condition {
+            if !condition.evaluate_bool(world, player_entity).unwrap_or(false) {
+                continue;
+            }
+        }
+        events.push(scheduled.event);
+    }
+}
+
+// Applies every queued event to the world, anything said is added to the output
+fn event_system(world: &World, game_output: &mut String) {
+    let events = world.borrow_resource_mut::<Events>().unwrap().drain();
+    let mut say = |text: &str| {
+        if !game_output.is_empty() {
+            game_output.push('\n');
+        }
+        game_output.push_str(text);
+    };
+    for event in events {
+        world.borrow_resource_mut::<RenderQueue>().unwrap().push(Message::debug(format!("event {:?}", event)));
+        match event {
+            GameEvent::Say(text) => say(&text),
+            GameEvent::GasolineEvaporated => {
+                let mut doors = world.borrow_component_mut::<DoorComponent>().unwrap();
+                for (_, door) in doors.iter_mut() {
+                    if door.is_gasolined() && door.is_frozen() {
+                        door.set_is_gasolined(false);
+                        say("The gasoline on the doorknob has evaporated in the wind");
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The cold, the clock it runs on and the events that happen as time passes
+pub struct SurvivalPlugin;
+
+impl Plugin<GameState> for SurvivalPlugin {
+    fn build(&self, app: &mut App<GameState>) {
+        app.register_reflect::<WarmthComponent>()
+            .add_startup_system(setup_survival_system)
+            // The time spent in menus or paused doesn't count against me
+            .add_enter_system(GameState::Playing, resume_clock_system)
+            .add_system_to_stage(GameState::Playing, Stage::First, playing_time_system)
+            // The cold doesn't wait while I talk
+            .add_system_to_stage(GameState::Dialogue, Stage::First, playing_time_system);
+    }
+}
+
+fn setup_survival_system(world: &mut World) {
+    let player_entity = get_player(world);
+    world.add_component_to_entity(player_entity, WarmthComponent::new(MAX_WARMTH));
+    world.add_resource(GameClock::new());
+    world.add_resource(Events::default());
+    let mut scheduler = Scheduler::default();
+    if let Err(err) = load_events(&mut scheduler, include_str!("events.txt")) {
+        println!("Failed to load events.txt, {}", err);
+        std::process::exit(1);
+    }
+    if let Err(err) = scheduler.validate(world, player_entity) {
+        println!("Failed to load events.txt, {}", err);
+        std::process::exit(1);
+    }
+    world.add_resource(scheduler);
+}
+
+fn resume_clock_system(world: &World, _: &[&str], _: &mut String) {
+    world.borrow_resource_mut::<GameClock>().unwrap().resume();
+}
+
+// Events due by now happen before the player gets to act
+fn playing_time_system(world: &World, _: &[&str], output: &mut String) {
+    time_system(world);
+    scheduler_system(world, get_player(world));
+    event_system(world, output);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endings::{EndingCategory, Endings, game_ending_system};
+    use crate::tests::{pass_time, run, setup_world, warmth_of};
+    use ecs_small::Value;
+
+    fn said(due: Vec<ScheduledEvent>) -> Vec<String> {
+        due.into_iter()
+            .map(|scheduled| match scheduled.event {
+                GameEvent::Say(text) => text,
+                other => format!("{:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scheduled_events_fire_at_their_time_and_repeat() {
+        let mut scheduler = Scheduler::default();
+        load_events(&mut scheduler, "event|at=5|say=\"once\"\nevent|every=3|say=\"again\"").unwrap();
+        assert!(scheduler.take_due(2).is_empty());
+        assert_eq!(said(scheduler.take_due(3)), vec!["again"]);
+        assert_eq!(said(scheduler.take_due(6)), vec!["once", "again"]);
+        assert!(scheduler.take_due(8).is_empty());
+
+        // A long jump forward only fires a repeating event once, then it carries on from the new time
+        assert_eq!(said(scheduler.take_due(100)), vec!["again"]);
+        assert!(scheduler.take_due(101).is_empty());
+        assert_eq!(said(scheduler.take_due(102)), vec!["again"]);
+    }
+
+    #[test]
+    fn scheduled_events_only_fire_while_their_condition_holds() {
+        let (mut world, player_entity) = setup_world();
+        let mut scheduler = Scheduler::default();
+        load_events(
+            &mut scheduler,
+            "event|at=1|when=\"player.area == \\\"cabin\\\"\"|say=\"inside\"\n\
+             event|at=1|when=\"player.area == \\\"outside\\\"\"|say=\"outside\"",
+        )
+        .unwrap();
+        world.add_resource(scheduler);
+        world.borrow_resource_mut::<Events>().unwrap().drain();
+        world.borrow_resource_mut::<GameClock>().unwrap().elapsed = Duration::from_secs(1);
+        scheduler_system(&world, player_entity);
+        assert_eq!(world.borrow_resource_mut::<Events>().unwrap().drain(), vec![GameEvent::Say(String::from("outside"))]);
+    }
+
+    #[test]
+    fn warmth_is_lost_to_the_chill_of_each_tile() {
+        let (world, player_entity) = setup_world();
+        pass_time(&world, 10);
+        assert_eq!(warmth_of(&world, player_entity).round(), MAX_WARMTH - 10.0);
+
+        // The edge of the woods is windier
+        world.set_path(player_entity, "location.x", Value::Int(1)).unwrap();
+        pass_time(&world, 10);
+        assert_eq!(warmth_of(&world, player_entity).round(), MAX_WARMTH - 30.0);
+        assert_eq!(world.borrow_component::<WarmthComponent>().unwrap()[player_entity].as_ref().unwrap().remaining_time(), Some(35));
+    }
+
+    #[test]
+    fn warmth_stays_between_zero_and_the_max() {
+        let mut warmth = WarmthComponent::new(MAX_WARMTH);
+        warmth.cool_down(5.0);
+        warmth.warm_up(LIGHTER_WARMTH);
+        assert_eq!(warmth.warmth, MAX_WARMTH);
+        warmth.cool_down(MAX_WARMTH * 2.0);
+        assert_eq!(warmth.warmth, 0.0);
+        assert!(warmth.is_frozen());
+    }
+
+    #[test]
+    fn freezing_ends_the_game_with_the_frozen_ending() {
+        let (world, player_entity) = setup_world();
+        pass_time(&world, MAX_WARMTH as u64 + 1);
+        assert!(!world.borrow_component::<PlayerComponent>().unwrap()[player_entity].as_ref().unwrap().is_alive);
+        let outcome = game_ending_system(&world, player_entity, &world.borrow_resource::<Endings>().unwrap().endings).unwrap();
+        assert_eq!((outcome.name.as_str(), outcome.category), ("frozen", EndingCategory::Death));
+    }
+
+    #[test]
+    fn the_lighter_warms_me_up() {
+        let (world, player_entity) = setup_world();
+        world.borrow_component_mut::<WarmthComponent>().unwrap()[player_entity].as_mut().unwrap().warmth = 50.0;
+        assert_eq!(run(&world, "use lighter"), "I cup my hands around the lighter's flame, it helps a little");
+        assert_eq!(warmth_of(&world, player_entity), 50.0 + LIGHTER_WARMTH);
+    }
+}
@@ -0,0 +1,2381 @@
+//! A small Entity/Component/System engine for text based games.
+//!
+//! Everything the game knows about lives in a [`World`], entities are plain ids with
+//! components stored against them, and resources hold the data that isn't tied to any one entity.
+//! Systems are plain functions run by a [`Schedule`] for whichever state the game is in.
+//! An [`App`] owns both, and [`Plugin`]s fill it in with their resources, systems and commands.
+//!
+//! ```no_run
+//! use ecs_small::{App, Plugin, World};
+//!
+//! #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+//! enum GameState {
+//!     Playing,
+//! }
+//!
+//! struct Score(u32);
+//!
+//! fn score_system(world: &World, input: &[&str], output: &mut String) {
+//!     let mut score = world.borrow_resource_mut::<Score>().unwrap();
+//!     score.0 += input.len() as u32;
+//!     output.push_str(&format!("Score: {}", score.0));
+//! }
+//!
+//! struct ScorePlugin;
+//!
+//! impl Plugin<GameState> for ScorePlugin {
+//!     fn build(&self, app: &mut App<GameState>) {
+//!         app.add_startup_system(|world| world.add_resource(Score(0)))
+//!             .add_system(GameState::Playing, score_system);
+//!     }
+//! }
+//!
+//! let mut app = App::new(GameState::Playing);
+//! app.add_plugin(ScorePlugin);
+//! app.run();
+//! ```
+
+// Lets the derive macros name this crate as ecs_small from inside it as well
+extern crate self as ecs_small;
+
+pub use ecs_small_derive::{Bundle, Component, Reflect};
+
+use std::any::{type_name, Any};
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+use std::io;
+use std::rc::Rc;
+use unicode_width::UnicodeWidthStr;
+
+// Lets the world keep every component storage in one list whatever the component type is
+// Implemented for the RefCell each storage is kept in
+trait ComponentVec {
+    fn as_any(&self) -> &dyn Any;
+    fn push_none(&mut self);
+    fn has(&self, entity: usize) -> bool;
+    fn borrow_state(&self) -> BorrowState;
+    // Both are None while the storage is borrowed mutably
+    fn bytes(&self) -> Option<usize>;
+    fn filled(&self) -> Option<usize>;
+}
+
+impl<S: ComponentStorage> ComponentVec for RefCell<S> {
+    // Borrow the storage of the component
+    fn as_any(&self) -> &dyn Any {
+        self as &dyn Any
+    }
+    // Make room for a new entity
+    fn push_none(&mut self) {
+        self.get_mut().push_none()
+    }
+    fn has(&self, entity: usize) -> bool {
+        self.try_borrow().is_ok_and(|components| components.component(entity).is_some())
+    }
+    fn borrow_state(&self) -> BorrowState {
+        if self.try_borrow_mut().is_ok() {
+            BorrowState::Free
+        } else if self.try_borrow().is_ok() {
+            BorrowState::Shared
+        } else {
+            BorrowState::Mutable
+        }
+    }
+    fn bytes(&self) -> Option<usize> {
+        Some(self.try_borrow().ok()?.bytes())
+    }
+    fn filled(&self) -> Option<usize> {
+        Some(self.try_borrow().ok()?.filled())
+    }
+}
+
+/// How a component's storage is laid out, picked with `#[component(storage = "...")]`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StorageKind {
+    /// A [`DenseStorage`], the default.
+    Dense,
+    /// A [`SparseStorage`].
+    Sparse,
+}
+
+/// Where the world keeps every entity's component of one type.
+///
+/// Storages are borrowed with [`World::borrow_component`], the methods here work the same on either kind.
+pub trait ComponentStorage: Default + 'static {
+    type Component;
+    const KIND: StorageKind;
+    /// Makes room for an entity that was just created.
+    fn push_none(&mut self);
+    fn component(&self, entity: usize) -> Option<&Self::Component>;
+    fn component_mut(&mut self, entity: usize) -> Option<&mut Self::Component>;
+    /// Gives the entity the component, replacing any it already had.
+    fn set_component(&mut self, entity: usize, component: Self::Component);
+    /// The entities that have the component, in the order they were created.
+    fn entities(&self) -> Vec<usize>;
+    /// How many entities have the component.
+    fn filled(&self) -> usize;
+    /// Roughly how much memory the storage has allocated.
+    fn bytes(&self) -> usize;
+}
+
+/// A slot for every entity, indexed by entity, for components most entities have.
+pub type DenseStorage<T> = Vec<Option<T>>;
+
+impl<T: 'static> ComponentStorage for DenseStorage<T> {
+    type Component = T;
+    const KIND: StorageKind = StorageKind::Dense;
+
+    fn push_none(&mut self) {
+        self.push(None)
+    }
+
+    fn component(&self, entity: usize) -> Option<&T> {
+        self.get(entity).and_then(Option::as_ref)
+    }
+
+    fn component_mut(&mut self, entity: usize) -> Option<&mut T> {
+        self.get_mut(entity).and_then(Option::as_mut)
+    }
+
+    fn set_component(&mut self, entity: usize, component: T) {
+        self[entity] = Some(component);
+    }
+
+    fn entities(&self) -> Vec<usize> {
+        self.iter().enumerate().filter(|(_, component)| component.is_some()).map(|(entity, _)| entity).collect()
+    }
+
+    fn filled(&self) -> usize {
+        self.iter().filter(|component| component.is_some()).count()
+    }
+
+    fn bytes(&self) -> usize {
+        self.capacity() * std::mem::size_of::<Option<T>>()
+    }
+}
+
+/// Only keeps the entities that have the component, for components few entities have such as a door.
+///
+/// ```
+/// use ecs_small::{Component, World};
+///
+/// #[derive(Component)]
+/// #[component(name = "door", storage = "sparse")]
+/// struct DoorComponent {
+///     is_open: bool,
+/// }
+///
+/// let mut world = World::new();
+/// world.register_component::<DoorComponent>();
+/// let wall = world.new_entity();
+/// let door = world.new_entity();
+/// world.add_component_to_entity(door, DoorComponent { is_open: false });
+/// let doors = world.borrow_component::<DoorComponent>().unwrap();
+/// assert!(doors.get(wall).is_none());
+/// assert_eq!(doors.iter().map(|(entity, door)| (entity, door.is_open)).collect::<Vec<_>>(), vec![(door, false)]);
+/// ```
+pub struct SparseStorage<T> {
+    components: BTreeMap<usize, T>,
+}
+
+impl<T> Default for SparseStorage<T> {
+    fn default() -> Self {
+        SparseStorage { components: BTreeMap::new() }
+    }
+}
+
+impl<T> SparseStorage<T> {
+    pub fn get(&self, entity: usize) -> Option<&T> {
+        self.components.get(&entity)
+    }
+
+    pub fn get_mut(&mut self, entity: usize) -> Option<&mut T> {
+        self.components.get_mut(&entity)
+    }
+
+    /// Gives the entity the component, handing back the one it had before.
+    pub fn insert(&mut self, entity: usize, component: T) -> Option<T> {
+        self.components.insert(entity, component)
+    }
+
+    pub fn remove(&mut self, entity: usize) -> Option<T> {
+        self.components.remove(&entity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Every entity with the component along with it, in the order the entities were created.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.components.iter().map(|(entity, component)| (*entity, component))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        self.components.iter_mut().map(|(entity, component)| (*entity, component))
+    }
+}
+
+impl<T: 'static> ComponentStorage for SparseStorage<T> {
+    type Component = T;
+    const KIND: StorageKind = StorageKind::Sparse;
+
+    // Entities without the component take up no room at all
+    fn push_none(&mut self) {}
+
+    fn component(&self, entity: usize) -> Option<&T> {
+        self.get(entity)
+    }
+
+    fn component_mut(&mut self, entity: usize) -> Option<&mut T> {
+        self.get_mut(entity)
+    }
+
+    fn set_component(&mut self, entity: usize, component: T) {
+        self.insert(entity, component);
+    }
+
+    fn entities(&self) -> Vec<usize> {
+        self.components.keys().copied().collect()
+    }
+
+    fn filled(&self) -> usize {
+        self.len()
+    }
+
+    // The tree's own bookkeeping isn't counted
+    fn bytes(&self) -> usize {
+        self.len() * std::mem::size_of::<(usize, T)>()
+    }
+}
+
+/// Something that can be added to an entity, implement it with `#[derive(Component)]`.
+///
+/// Components are kept in a [`DenseStorage`] unless `#[component(storage = "sparse")]` picks a [`SparseStorage`].
+///
+/// ```
+/// use ecs_small::{Component, World};
+///
+/// #[derive(Component)]
+/// #[component(name = "door")]
+/// struct DoorComponent {
+///     is_open: bool,
+/// }
+///
+/// let mut world = World::new();
+/// world.register_component::<DoorComponent>();
+/// let door = world.new_entity();
+/// world.add_component_to_entity(door, DoorComponent { is_open: false });
+/// assert_eq!(world.component_info()[0].name, "door");
+/// ```
+pub trait Component: Sized + 'static {
+    /// The name the component is known by, the type's name unless `#[component(name = "...")]` says otherwise.
+    const NAME: &'static str;
+    type Storage: ComponentStorage<Component = Self>;
+}
+
+/// A group of components added to an entity together, implement it with `#[derive(Bundle)]`.
+pub trait Bundle {
+    fn add_to_entity(self, world: &mut World, entity: usize);
+}
+
+/// A field's value read or written through [`Reflect`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl Value {
+    /// Reads a value typed in by hand, `true`/`false` and numbers are recognised and anything else is text.
+    /// Quotes around text are dropped.
+    pub fn parse(input: &str) -> Value {
+        let input = input.trim();
+        if let Ok(value) = input.parse::<bool>() {
+            Value::Bool(value)
+        } else if let Ok(value) = input.parse::<i64>() {
+            Value::Int(value)
+        } else if let Ok(value) = input.parse::<f64>() {
+            Value::Float(value)
+        } else {
+            let text = input.strip_prefix('"').and_then(|text| text.strip_suffix('"')).unwrap_or(input);
+            Value::Text(text.to_string())
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "integer",
+            Value::Float(_) => "number",
+            Value::Text(_) => "text",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Text(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+/// Why a path couldn't be read or written.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ReflectError {
+    /// Nothing at the path, holds the part of the path that wasn't found
+    NoField(String),
+    /// The path stops at something with fields of its own rather than a value
+    NotAValue(Vec<&'static str>),
+    WrongType { expected: &'static str, found: Value },
+    NoComponent(String),
+    /// The component exists but the entity doesn't have one
+    NotOnEntity { entity: usize, component: &'static str },
+    /// The number is too big for a [`Value::Int`], holds the number as it was stored
+    OutOfRange(String),
+}
+
+impl fmt::Display for ReflectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReflectError::NoField(field) => write!(f, "there is no field called \"{}\"", field),
+            ReflectError::NotAValue(fields) => write!(f, "it has fields of its own, pick one of {}", fields.join(", ")),
+            ReflectError::WrongType { expected, found } => write!(f, "expected a {} but got the {} {}", expected, found.kind(), found),
+            ReflectError::NoComponent(name) => write!(f, "there is no component called \"{}\"", name),
+            ReflectError::NotOnEntity { entity, component } => write!(f, "entity {} doesn't have a {}", entity, component),
+            ReflectError::OutOfRange(number) => write!(f, "{} is too big to show as an integer", number),
+        }
+    }
+}
+
+/// A field's name and the type it was declared with.
+#[derive(Clone, PartialEq, Debug)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    pub type_name: &'static str,
+}
+
+/// Lets a value's fields be read and written by path at runtime, implement it with `#[derive(Reflect)]`.
+///
+/// Paths are field names joined with dots, such as `"position.x"`, and the empty path is the value itself.
+/// Fields marked `#[reflect(skip)]` can't be reached.
+///
+/// ```
+/// use ecs_small::{Reflect, Value};
+///
+/// #[derive(Reflect)]
+/// struct Position {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let mut position = Position { x: 1, y: 2 };
+/// position.set_path("y", Value::Int(5)).unwrap();
+/// assert_eq!(position.get_path("y"), Ok(Value::Int(5)));
+/// assert!(position.get_path("z").is_err());
+/// ```
+pub trait Reflect {
+    /// The fields that can be reached, empty for plain values.
+    fn fields(&self) -> Vec<FieldInfo>;
+    fn get_path(&self, path: &str) -> Result<Value, ReflectError>;
+    fn set_path(&mut self, path: &str, value: Value) -> Result<(), ReflectError>;
+}
+
+/// Splits the first field off a path, used by `#[derive(Reflect)]`.
+pub fn split_path(path: &str) -> (&str, &str) {
+    path.split_once('.').unwrap_or((path, ""))
+}
+
+// Plain values end a path, there's nothing further in to go
+fn check_leaf(path: &str) -> Result<(), ReflectError> {
+    match path {
+        "" => Ok(()),
+        _ => Err(ReflectError::NoField(path.to_string())),
+    }
+}
+
+impl Reflect for bool {
+    fn fields(&self) -> Vec<FieldInfo> {
+        Vec::new()
+    }
+
+    fn get_path(&self, path: &str) -> Result<Value, ReflectError> {
+        check_leaf(path)?;
+        Ok(Value::Bool(*self))
+    }
+
+    fn set_path(&mut self, path: &str, value: Value) -> Result<(), ReflectError> {
+        check_leaf(path)?;
+        match value {
+            Value::Bool(value) => *self = value,
+            found => return Err(ReflectError::WrongType { expected: "bool", found }),
+        }
+        Ok(())
+    }
+}
+
+macro_rules! reflect_integer {
+    ($($ty:ty),*) => {$(
+        impl Reflect for $ty {
+            fn fields(&self) -> Vec<FieldInfo> {
+                Vec::new()
+            }
+
+            fn get_path(&self, path: &str) -> Result<Value, ReflectError> {
+                check_leaf(path)?;
+                i64::try_from(*self).map(Value::Int).map_err(|_| ReflectError::OutOfRange(self.to_string()))
+            }
+
+            fn set_path(&mut self, path: &str, value: Value) -> Result<(), ReflectError> {
+                check_leaf(path)?;
+                match value {
+                    Value::Int(int) => match <$ty>::try_from(int) {
+                        Ok(int) => *self = int,
+                        Err(_) => return Err(ReflectError::WrongType { expected: stringify!($ty), found: value }),
+                    },
+                    found => return Err(ReflectError::WrongType { expected: stringify!($ty), found }),
+                }
+                Ok(())
+            }
+        }
+    )*};
+}
+
+reflect_integer!(i32, i64, u32, u64, usize);
+
+macro_rules! reflect_float {
+    ($($ty:ty),*) => {$(
+        impl Reflect for $ty {
+            fn fields(&self) -> Vec<FieldInfo> {
+                Vec::new()
+            }
+
+            fn get_path(&self, path: &str) -> Result<Value, ReflectError> {
+                check_leaf(path)?;
+                Ok(Value::Float(*self as f64))
+            }
+
+            fn set_path(&mut self, path: &str, value: Value) -> Result<(), ReflectError> {
+                check_leaf(path)?;
+                match value {
+                    Value::Float(float) => *self = float as $ty,
+                    Value::Int(int) => *self = int as $ty,
+                    found => return Err(ReflectError::WrongType { expected: stringify!($ty), found }),
+                }
+                Ok(())
+            }
+        }
+    )*};
+}
+
+reflect_float!(f32, f64);
+
+impl Reflect for String {
+    fn fields(&self) -> Vec<FieldInfo> {
+        Vec::new()
+    }
+
+    fn get_path(&self, path: &str) -> Result<Value, ReflectError> {
+        check_leaf(path)?;
+        Ok(Value::Text(self.clone()))
+    }
+
+    fn set_path(&mut self, path: &str, value: Value) -> Result<(), ReflectError> {
+        check_leaf(path)?;
+        match value {
+            Value::Text(text) => *self = text,
+            found => return Err(ReflectError::WrongType { expected: "String", found }),
+        }
+        Ok(())
+    }
+}
+
+// How the world reaches a reflected component without knowing its type
+#[derive(Clone, Copy, Debug)]
+pub struct ReflectFns {
+    fields: fn(&World, usize) -> Result<Vec<FieldInfo>, ReflectError>,
+    get: fn(&World, usize, &str) -> Result<Value, ReflectError>,
+    set: fn(&World, usize, &str, Value) -> Result<(), ReflectError>,
+}
+
+impl ReflectFns {
+    fn of<ComponentType: Component + Reflect>() -> Self {
+        ReflectFns {
+            fields: |world, entity| Ok(reflect_on::<ComponentType>(world, entity)?.fields()),
+            get: |world, entity, path| reflect_on::<ComponentType>(world, entity)?.get_path(path),
+            set: |world, entity, path, value| {
+                let mut components = world
+                    .borrow_component_mut::<ComponentType>()
+                    .ok_or_else(|| ReflectError::NoComponent(ComponentType::NAME.to_string()))?;
+                match components.component_mut(entity) {
+                    Some(component) => component.set_path(path, value),
+                    None => Err(ReflectError::NotOnEntity { entity, component: ComponentType::NAME }),
+                }
+            },
+        }
+    }
+}
+
+fn reflect_on<ComponentType: Component>(world: &World, entity: usize) -> Result<Ref<'_, ComponentType>, ReflectError> {
+    let components = world
+        .borrow_component::<ComponentType>()
+        .ok_or_else(|| ReflectError::NoComponent(ComponentType::NAME.to_string()))?;
+    Ref::filter_map(components, |components| components.component(entity))
+        .map_err(|_| ReflectError::NotOnEntity { entity, component: ComponentType::NAME })
+}
+
+/// Whether a component storage is borrowed at the moment.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BorrowState {
+    Free,
+    Shared,
+    Mutable,
+}
+
+impl BorrowState {
+    fn as_str(self) -> &'static str {
+        match self {
+            BorrowState::Free => "free",
+            BorrowState::Shared => "shared",
+            BorrowState::Mutable => "mutable",
+        }
+    }
+}
+
+/// How full a component storage is and how much memory it takes up.
+#[derive(Clone, Debug)]
+pub struct StorageStats {
+    pub name: &'static str,
+    pub type_name: &'static str,
+    pub storage: StorageKind,
+    /// How many entities have the component, unknown while the storage is borrowed mutably
+    pub filled: Option<usize>,
+    pub len: usize,
+    /// The memory the storage has allocated, also unknown while it's borrowed mutably
+    pub bytes: Option<usize>,
+    pub borrow: BorrowState,
+}
+
+impl StorageStats {
+    pub fn fill_ratio(&self) -> Option<f64> {
+        match (self.filled, self.len) {
+            (Some(_), 0) => Some(0.0),
+            (Some(filled), len) => Some(filled as f64 / len as f64),
+            (None, _) => None,
+        }
+    }
+}
+
+/// One of an entity's components as shown in a dump.
+#[derive(Clone, Debug)]
+pub struct ComponentDump {
+    pub name: &'static str,
+    pub type_name: &'static str,
+    /// The `Debug` or reflected value, `None` for components registered with neither
+    pub value: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EntityDump {
+    pub entity: usize,
+    pub components: Vec<ComponentDump>,
+}
+
+/// Everything in the world at the time [`World::dump`] was called.
+#[derive(Clone, Debug)]
+pub struct WorldDump {
+    pub entities: Vec<EntityDump>,
+    pub storages: Vec<StorageStats>,
+    pub resources: usize,
+}
+
+impl WorldDump {
+    pub fn to_text(&self) -> String {
+        let mut lines = Vec::new();
+        for entity in &self.entities {
+            lines.push(format!("Entity {}", entity.entity));
+            for component in &entity.components {
+                match &component.value {
+                    Some(value) => lines.push(format!("  {} ({}) {}", component.name, component.type_name, value)),
+                    None => lines.push(format!("  {} ({})", component.name, component.type_name)),
+                }
+            }
+        }
+        lines.push(format!("{} entities, {} resources", self.entities.len(), self.resources));
+        for stats in &self.storages {
+            let filled = match (stats.filled, stats.fill_ratio()) {
+                (Some(filled), Some(ratio)) => format!("{}/{} filled ({:.0}%)", filled, stats.len, ratio * 100.0),
+                _ => format!("?/{} filled", stats.len),
+            };
+            let bytes = stats.bytes.map(|bytes| bytes.to_string()).unwrap_or_else(|| String::from("?"));
+            lines.push(format!(
+                "  {} {:?} {}, {} bytes, borrow {}",
+                stats.name,
+                stats.storage,
+                filled,
+                bytes,
+                stats.borrow.as_str()
+            ));
+        }
+        lines.join("\n")
+    }
+
+    pub fn to_json(&self) -> String {
+        let entities: Vec<String> = self
+            .entities
+            .iter()
+            .map(|entity| {
+                let components: Vec<String> = entity
+                    .components
+                    .iter()
+                    .map(|component| {
+                        format!(
+                            "{{\"name\":{},\"type\":{},\"value\":{}}}",
+                            json_string(component.name),
+                            json_string(component.type_name),
+                            component.value.as_deref().map(json_string).unwrap_or_else(|| String::from("null"))
+                        )
+                    })
+                    .collect();
+                format!("{{\"entity\":{},\"components\":[{}]}}", entity.entity, components.join(","))
+            })
+            .collect();
+        let storages: Vec<String> = self
+            .storages
+            .iter()
+            .map(|stats| {
+                let optional = |value: Option<String>| value.unwrap_or_else(|| String::from("null"));
+                format!(
+                    "{{\"name\":{},\"type\":{},\"storage\":{},\"filled\":{},\"len\":{},\"fill_ratio\":{},\"bytes\":{},\"borrow\":{}}}",
+                    json_string(stats.name),
+                    json_string(stats.type_name),
+                    json_string(&format!("{:?}", stats.storage).to_lowercase()),
+                    optional(stats.filled.map(|filled| filled.to_string())),
+                    stats.len,
+                    optional(stats.fill_ratio().map(|ratio| ratio.to_string())),
+                    optional(stats.bytes.map(|bytes| bytes.to_string())),
+                    json_string(stats.borrow.as_str())
+                )
+            })
+            .collect();
+        format!(
+            "{{\"entities\":[{}],\"storages\":[{}],\"resources\":{}}}",
+            entities.join(","),
+            storages.join(","),
+            self.resources
+        )
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// What the world knows about each registered component type.
+#[derive(Clone, Debug)]
+pub struct ComponentInfo {
+    pub name: &'static str,
+    pub type_name: &'static str,
+    pub storage: StorageKind,
+    /// Set for components registered with [`World::register_reflect`]
+    pub reflect: Option<ReflectFns>,
+    /// Set for components registered with [`World::register_debug`]
+    pub debug: Option<DebugFn>,
+}
+
+/// Formats an entity's component with `Debug`, `None` if the entity doesn't have one.
+pub type DebugFn = fn(&World, usize) -> Option<String>;
+
+/// Contains all of the data used by the game.
+///
+/// Entities are ids handed out by [`World::new_entity`]. Each component type has a storage of its own,
+/// by default a `Vec<Option<T>>` indexed by entity so an entity without the component holds `None` there.
+/// Component types have to be registered before they're added to an entity.
+/// Resources are single pieces of data not tied to an entity, such as a game clock.
+#[derive(Default)]
+pub struct World {
+    entities_count: usize,
+    components: Vec<Box<dyn ComponentVec>>,
+    // Kept in the same order as the component vectors
+    component_info: Vec<ComponentInfo>,
+    resources: Vec<Box<dyn Any>>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a resource, there is only ever one of each type so adding it again replaces the old one.
+    pub fn add_resource<ResourceType: 'static>(&mut self, resource: ResourceType) {
+        for existing in self.resources.iter_mut() {
+            if let Some(existing) = existing.downcast_mut::<RefCell<ResourceType>>() {
+                *existing.get_mut() = resource;
+                return;
+            }
+        }
+        self.resources.push(Box::new(RefCell::new(resource)));
+    }
+
+    /// Borrows a resource, `None` if it was never added.
+    pub fn borrow_resource<ResourceType: 'static>(&self) -> Option<Ref<'_, ResourceType>> {
+        self.resources
+            .iter()
+            .find_map(|resource| resource.downcast_ref::<RefCell<ResourceType>>())
+            .map(|resource| resource.borrow())
+    }
+
+    /// Mutably borrows a resource, `None` if it was never added.
+    ///
+    /// Panics if the resource is already borrowed, the same as a `RefCell`.
+    pub fn borrow_resource_mut<ResourceType: 'static>(&self) -> Option<RefMut<'_, ResourceType>> {
+        self.resources
+            .iter()
+            .find_map(|resource| resource.downcast_ref::<RefCell<ResourceType>>())
+            .map(|resource| resource.borrow_mut())
+    }
+
+    /// Creates an entity with no components and returns its id.
+    pub fn new_entity(&mut self) -> usize {
+        let entity_id = self.entities_count;
+        // Make room for the new entity id in every component type we have available
+        for component in self.components.iter_mut() {
+            component.push_none();
+        }
+        self.entities_count += 1;
+        entity_id
+    }
+
+    /// How many entities have been created.
+    pub fn entity_count(&self) -> usize {
+        self.entities_count
+    }
+
+    /// Makes room for a component type, registering it again does nothing.
+    pub fn register_component<ComponentType: Component>(&mut self) {
+        if self.borrow_component::<ComponentType>().is_some() {
+            return;
+        }
+        // Dense storages need a slot for every entity that's already been created
+        let mut new_component = ComponentType::Storage::default();
+        for _ in 0..self.entities_count {
+            new_component.push_none();
+        }
+        self.components.push(Box::new(RefCell::new(new_component)));
+        self.component_info.push(ComponentInfo {
+            name: ComponentType::NAME,
+            type_name: type_name::<ComponentType>(),
+            storage: <ComponentType::Storage as ComponentStorage>::KIND,
+            reflect: None,
+            debug: None,
+        });
+    }
+
+    /// Registers a component so dumps of the world show its `Debug` value.
+    pub fn register_debug<ComponentType: Component + fmt::Debug>(&mut self) {
+        self.register_component::<ComponentType>();
+        let info = self
+            .component_info
+            .iter_mut()
+            .find(|info| info.type_name == type_name::<ComponentType>())
+            .unwrap();
+        info.debug = Some(|world, entity| {
+            let components = world.borrow_component::<ComponentType>()?;
+            components.component(entity).map(|component| format!("{:?}", component))
+        });
+    }
+
+    /// Registers a component so its fields can be reached by path, see [`World::get_path`].
+    pub fn register_reflect<ComponentType: Component + Reflect>(&mut self) {
+        self.register_component::<ComponentType>();
+        let info = self
+            .component_info
+            .iter_mut()
+            .find(|info| info.type_name == type_name::<ComponentType>())
+            .unwrap();
+        info.reflect = Some(ReflectFns::of::<ComponentType>());
+    }
+
+    /// The names of the components an entity has.
+    pub fn components_of(&self, entity: usize) -> Vec<&'static str> {
+        self.component_info
+            .iter()
+            .filter(|info| self.has_component(entity, info))
+            .map(|info| info.name)
+            .collect()
+    }
+
+    /// Whether the entity has the component described by `info`.
+    pub fn has_component(&self, entity: usize, info: &ComponentInfo) -> bool {
+        self.component_info
+            .iter()
+            .position(|registered| registered.type_name == info.type_name)
+            .is_some_and(|index| self.components[index].has(entity))
+    }
+
+    /// Every registered component type, in the order they were registered.
+    pub fn component_info(&self) -> &[ComponentInfo] {
+        &self.component_info
+    }
+
+    /// Gives an entity a component, replacing any it already had of the same type.
+    ///
+    /// Panics if the component type hasn't been registered.
+    pub fn add_component_to_entity<ComponentType: Component>(&mut self, entity: usize, component: ComponentType) {
+        match self.borrow_component_mut::<ComponentType>() {
+            Some(mut components) => components.set_component(entity, component),
+            None => panic!(
+                "{} has to be registered before it can be added to an entity",
+                type_name::<ComponentType>()
+            ),
+        }
+    }
+
+    /// Creates an entity with every component in the bundle and returns its id.
+    pub fn spawn<B: Bundle>(&mut self, bundle: B) -> usize {
+        let entity = self.new_entity();
+        bundle.add_to_entity(self, entity);
+        entity
+    }
+
+    /// Mutably borrows the storage for a component type, `None` if it was never registered.
+    ///
+    /// For dense components this is every entity's slot, a `Vec<Option<T>>` indexed by entity.
+    pub fn borrow_component_mut<ComponentType: Component>(&self) -> Option<RefMut<'_, ComponentType::Storage>> {
+        self.components
+            .iter()
+            .find_map(|component_vec| component_vec.as_any().downcast_ref::<RefCell<ComponentType::Storage>>())
+            .map(|component_vec| component_vec.borrow_mut())
+    }
+
+    /// Borrows the storage for a component type, `None` if it was never registered.
+    pub fn borrow_component<ComponentType: Component>(&self) -> Option<Ref<'_, ComponentType::Storage>> {
+        self.components
+            .iter()
+            .find_map(|component_vec| component_vec.as_any().downcast_ref::<RefCell<ComponentType::Storage>>())
+            .map(|component_vec| component_vec.borrow())
+    }
+
+    /// The entities that have a component of the given type, in the order they were created.
+    pub fn query<ComponentType: Component>(&self) -> Vec<usize> {
+        match self.borrow_component::<ComponentType>() {
+            Some(components) => components.entities(),
+            None => Vec::new(),
+        }
+    }
+
+    // The component a path starts with, along with the rest of the path
+    fn reflected<'a>(&self, path: &'a str) -> Result<(ReflectFns, &'a str), ReflectError> {
+        let (name, rest) = split_path(path);
+        self.component_info
+            .iter()
+            .find_map(|info| match info.reflect {
+                Some(reflect) if info.name == name => Some((reflect, rest)),
+                _ => None,
+            })
+            .ok_or_else(|| ReflectError::NoComponent(name.to_string()))
+    }
+
+    /// Every entity with its components, along with how each component storage is doing.
+    ///
+    /// Storages that are borrowed mutably at the time can't be looked in, so their components are left out.
+    pub fn dump(&self) -> WorldDump {
+        let entities = (0..self.entities_count)
+            .map(|entity| EntityDump {
+                entity,
+                components: self
+                    .component_info
+                    .iter()
+                    .zip(&self.components)
+                    .filter(|(_, component_vec)| component_vec.has(entity))
+                    .map(|(info, _)| ComponentDump {
+                        name: info.name,
+                        type_name: info.type_name,
+                        value: self.component_value(entity, info),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let storages = self
+            .component_info
+            .iter()
+            .zip(&self.components)
+            .map(|(info, component_vec)| StorageStats {
+                name: info.name,
+                type_name: info.type_name,
+                storage: info.storage,
+                filled: component_vec.filled(),
+                // Sparse storages only hold what's filled, but every entity could be so the ratios compare
+                len: self.entities_count,
+                bytes: component_vec.bytes(),
+                borrow: component_vec.borrow_state(),
+            })
+            .collect();
+        WorldDump { entities, storages, resources: self.resources.len() }
+    }
+
+    // Debug is preferred, otherwise the reflected fields are listed
+    fn component_value(&self, entity: usize, info: &ComponentInfo) -> Option<String> {
+        match info.debug {
+            Some(debug) => debug(self, entity),
+            None if info.reflect.is_some() => Some(self.reflected_value(entity, info.name)),
+            None => None,
+        }
+    }
+
+    // Anything with fields of its own is shown the same way as the component
+    fn reflected_value(&self, entity: usize, path: &str) -> String {
+        match self.get_path(entity, path) {
+            Ok(value) => value.to_string(),
+            Err(ReflectError::NotAValue(fields)) => {
+                let values: Vec<String> = fields
+                    .iter()
+                    .map(|field| format!("{}: {}", field, self.reflected_value(entity, &format!("{}.{}", path, field))))
+                    .collect();
+                format!("{{ {} }}", values.join(", "))
+            }
+            Err(_) => String::from(".."),
+        }
+    }
+
+    /// The fields of an entity's component, looked up by the component's name.
+    pub fn fields_of(&self, entity: usize, component: &str) -> Result<Vec<FieldInfo>, ReflectError> {
+        let (reflect, _) = self.reflected(component)?;
+        (reflect.fields)(self, entity)
+    }
+
+    /// Reads a field of an entity's component by a path starting with the component's name, such as `"door.is_frozen"`.
+    pub fn get_path(&self, entity: usize, path: &str) -> Result<Value, ReflectError> {
+        let (reflect, rest) = self.reflected(path)?;
+        (reflect.get)(self, entity, rest)
+    }
+
+    /// Writes a field of an entity's component by a path starting with the component's name.
+    pub fn set_path(&self, entity: usize, path: &str, value: Value) -> Result<(), ReflectError> {
+        let (reflect, rest) = self.reflected(path)?;
+        (reflect.set)(self, entity, rest, value)
+    }
+}
+
+/// The state the game is in, stored as a resource.
+///
+/// Systems ask for a change with [`State::set`] and it happens once the frame's systems have run.
+pub struct State<S> {
+    current: S,
+    next: Option<S>,
+}
+
+impl<S: Copy> State<S> {
+    pub fn new(initial: S) -> Self {
+        State { current: initial, next: None }
+    }
+
+    pub fn get(&self) -> S {
+        self.current
+    }
+
+    pub fn set(&mut self, next: S) {
+        self.next = Some(next);
+    }
+}
+
+/// A system is handed the world, the frame's input split into words and the text to print.
+///
+/// Whatever a system writes is queued as narration, systems that need a say in how their
+/// messages are shown push them onto the world's [`RenderQueue`] themselves.
+pub type StateSystem = fn(&World, &[&str], &mut String);
+
+/// Fills in a fresh world before the first frame.
+pub type StartupSystem = fn(&mut World);
+
+/// When in the frame a system runs, systems in the same stage run in the order they were added.
+#[derive(Clone, Copy)]
+pub enum Stage {
+    First,
+    Update,
+    Last,
+}
+
+#[derive(Default)]
+struct StateSystems {
+    enter: Vec<StateSystem>,
+    first: Vec<StateSystem>,
+    update: Vec<StateSystem>,
+    last: Vec<StateSystem>,
+    exit: Vec<StateSystem>,
+}
+
+/// The systems to run for each state, along with the ones to run when a state is entered or left.
+pub struct Schedule<S> {
+    sets: HashMap<S, StateSystems>,
+}
+
+impl<S: Copy + Eq + Hash + 'static> Default for Schedule<S> {
+    fn default() -> Self {
+        Schedule { sets: HashMap::new() }
+    }
+}
+
+impl<S: Copy + Eq + Hash + 'static> Schedule<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_enter(&mut self, state: S, system: StateSystem) -> &mut Self {
+        self.sets.entry(state).or_default().enter.push(system);
+        self
+    }
+
+    pub fn on_update(&mut self, state: S, stage: Stage, system: StateSystem) -> &mut Self {
+        let set = self.sets.entry(state).or_default();
+        match stage {
+            Stage::First => set.first.push(system),
+            Stage::Update => set.update.push(system),
+            Stage::Last => set.last.push(system),
+        }
+        self
+    }
+
+    pub fn on_exit(&mut self, state: S, system: StateSystem) -> &mut Self {
+        self.sets.entry(state).or_default().exit.push(system);
+        self
+    }
+
+    // Each system's output is a message of its own
+    fn run_systems(systems: &[StateSystem], world: &World, input: &[&str]) {
+        for system in systems {
+            let mut system_output = String::new();
+            system(world, input, &mut system_output);
+            if !system_output.is_empty() {
+                let mut queue = world.borrow_resource_mut::<RenderQueue>().expect("The world needs a RenderQueue to run systems");
+                queue.push(Message::narration(system_output));
+            }
+        }
+    }
+
+    /// Enters the state the world starts in.
+    pub fn start(&self, world: &World) {
+        let current = world.borrow_resource::<State<S>>().unwrap().get();
+        if let Some(set) = self.sets.get(&current) {
+            Self::run_systems(&set.enter, world, &[]);
+        }
+        self.apply_transitions(world);
+    }
+
+    /// Runs the current state's systems on the input, then moves on to whatever state they asked for.
+    pub fn run(&self, world: &World, input: &[&str]) {
+        let current = world.borrow_resource::<State<S>>().unwrap().get();
+        if let Some(set) = self.sets.get(&current) {
+            for systems in [&set.first, &set.update, &set.last] {
+                Self::run_systems(systems, world, input);
+            }
+        }
+        self.apply_transitions(world);
+    }
+
+    /// Runs the exit and enter systems for any state change that was asked for.
+    pub fn apply_transitions(&self, world: &World) {
+        // Entering a state can ask for another change straight away, so keep going until it settles
+        loop {
+            let (current, next) = {
+                let mut state = world.borrow_resource_mut::<State<S>>().unwrap();
+                match state.next.take() {
+                    Some(next) if next != state.current => (state.current, next),
+                    _ => return,
+                }
+            };
+            if let Some(set) = self.sets.get(&current) {
+                Self::run_systems(&set.exit, world, &[]);
+            }
+            world.borrow_resource_mut::<State<S>>().unwrap().current = next;
+            if let Some(set) = self.sets.get(&next) {
+                Self::run_systems(&set.enter, world, &[]);
+            }
+        }
+    }
+}
+
+/// Things the app takes care of between frames since systems can't replace the world themselves.
+#[derive(Default)]
+pub struct Session {
+    pub quit: bool,
+    pub restart: bool,
+}
+
+/// Every command a plugin has registered along with the line of help that goes with it.
+pub struct CommandHelp {
+    pub commands: Vec<(&'static str, &'static str)>,
+}
+
+impl CommandHelp {
+    pub fn render(&self) -> String {
+        let names: Vec<&str> = self.commands.iter().map(|(name, _)| *name).collect();
+        let mut help = format!("> Available Commands {{{{{}}}}}", names.join(", "));
+        for (_, line) in self.commands.iter().filter(|(_, line)| !line.is_empty()) {
+            help.push('\n');
+            help.push_str(line);
+        }
+        help
+    }
+}
+
+/// A developer command run on the world directly, handed the words after the command's name.
+pub type DevCommandFn = fn(&mut World, &[&str]) -> Result<String, String>;
+
+struct DevCommand {
+    name: &'static str,
+    usage: &'static str,
+    run: DevCommandFn,
+}
+
+/// Developer commands for testing, typed with a leading `/` such as `/inspect 3`.
+///
+/// They sit outside the game's own commands and only run once the console is enabled.
+/// `list entities`, `inspect <entity>` and `set <entity> <path> <value>` work on any world,
+/// games add their own with [`App::add_dev_command`].
+pub struct DevConsole {
+    enabled: bool,
+    commands: Vec<DevCommand>,
+}
+
+impl Default for DevConsole {
+    fn default() -> Self {
+        let mut console = DevConsole { enabled: false, commands: Vec::new() };
+        console.add("list", "list entities", list_entities_command);
+        console.add("inspect", "inspect <entity>", inspect_command);
+        console.add("set", "set <entity> <component.field> <value>", set_command);
+        console.add("dump", "dump [json]", dump_command);
+        console
+    }
+}
+
+impl DevConsole {
+    fn add(&mut self, name: &'static str, usage: &'static str, run: DevCommandFn) {
+        self.commands.push(DevCommand { name, usage, run });
+    }
+
+    fn help(&self) -> String {
+        let usages: Vec<&str> = self.commands.iter().map(|command| command.usage).collect();
+        format!("Developer commands: /help, /{}", usages.join(", /"))
+    }
+
+    /// Runs the input if it's a developer command, `None` if it's meant for the game.
+    pub fn run(&self, world: &mut World, input: &[&str]) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        let name = input.first()?.strip_prefix('/')?;
+        if name == "help" {
+            return Some(self.help());
+        }
+        let output = match self.commands.iter().find(|command| command.name == name) {
+            Some(command) => (command.run)(world, &input[1..]).unwrap_or_else(|err| format!("{}\nUsage: /{}", err, command.usage)),
+            None => format!("There's no developer command called \"{}\"\n{}", name, self.help()),
+        };
+        Some(output)
+    }
+}
+
+/// Reads an entity id typed into the console, checking it exists.
+pub fn parse_entity(world: &World, input: Option<&&str>) -> Result<usize, String> {
+    let input = input.ok_or("Which entity?")?;
+    match input.parse::<usize>() {
+        Ok(entity) if entity < world.entity_count() => Ok(entity),
+        _ => Err(format!("There's no entity {}", input)),
+    }
+}
+
+fn list_entities_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    if args != ["entities"] {
+        return Err(String::from("List what?"));
+    }
+    let lines: Vec<String> = (0..world.entity_count())
+        .map(|entity| format!("{}: {}", entity, world.components_of(entity).join(", ")))
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+fn inspect_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let entity = parse_entity(world, args.first())?;
+    let mut lines = vec![format!("Entity {}", entity)];
+    for info in world.component_info().iter().filter(|info| world.has_component(entity, info)) {
+        lines.push(format!("  {}", info.name));
+        // Components that aren't reflected can only be listed
+        let fields = world.fields_of(entity, info.name).unwrap_or_default();
+        for field in fields {
+            let path = format!("{}.{}", info.name, field.name);
+            let value = match world.get_path(entity, &path) {
+                Ok(value) => value.to_string(),
+                Err(err) => format!("({})", err),
+            };
+            lines.push(format!("    {}: {} = {}", field.name, field.type_name, value));
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+fn dump_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    match args {
+        [] => Ok(world.dump().to_text()),
+        ["json"] => Ok(world.dump().to_json()),
+        _ => Err(String::from("Dump as what?")),
+    }
+}
+
+fn set_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let entity = parse_entity(world, args.first())?;
+    let (path, value) = match args {
+        [_, path, value @ ..] if !value.is_empty() => (*path, Value::parse(&value.join(" "))),
+        _ => return Err(String::from("Set what?")),
+    };
+    world.set_path(entity, path, value).map_err(|err| format!("Can't set {}, {}", path, err))?;
+    Ok(format!("{} on entity {} is now {}", path, entity, world.get_path(entity, path).unwrap()))
+}
+
+/// A feature of the game bundled up with everything it needs, its resources, systems and commands.
+pub trait Plugin<S> {
+    fn build(&self, app: &mut App<S>);
+}
+
+/// Owns the world and the schedule that runs on it, plugins fill both in.
+pub struct App<S> {
+    pub world: World,
+    pub schedule: Schedule<S>,
+    registrations: Vec<StartupSystem>,
+    startup: Vec<StartupSystem>,
+    commands: Vec<(&'static str, &'static str)>,
+    console: DevConsole,
+    backend: Box<dyn RenderBackend>,
+    initial_state: S,
+    restart_state: S,
+}
+
+impl<S: Copy + Eq + Hash + 'static> App<S> {
+    pub fn new(initial_state: S) -> Self {
+        App {
+            world: World::new(),
+            schedule: Schedule::new(),
+            registrations: Vec::new(),
+            startup: Vec::new(),
+            commands: Vec::new(),
+            console: DevConsole::default(),
+            backend: Box::new(LineBackend),
+            initial_state,
+            restart_state: initial_state,
+        }
+    }
+
+    pub fn add_plugin(&mut self, plugin: impl Plugin<S>) -> &mut Self {
+        plugin.build(self);
+        self
+    }
+
+    /// Startup systems run in the order they were added whenever the world is built.
+    pub fn add_startup_system(&mut self, system: StartupSystem) -> &mut Self {
+        self.startup.push(system);
+        self
+    }
+
+    pub fn add_system(&mut self, state: S, system: StateSystem) -> &mut Self {
+        self.add_system_to_stage(state, Stage::Update, system)
+    }
+
+    pub fn add_system_to_stage(&mut self, state: S, stage: Stage, system: StateSystem) -> &mut Self {
+        self.schedule.on_update(state, stage, system);
+        self
+    }
+
+    pub fn add_enter_system(&mut self, state: S, system: StateSystem) -> &mut Self {
+        self.schedule.on_enter(state, system);
+        self
+    }
+
+    pub fn add_exit_system(&mut self, state: S, system: StateSystem) -> &mut Self {
+        self.schedule.on_exit(state, system);
+        self
+    }
+
+    /// Registers a command for the help, leave the line empty for commands already explained by another one.
+    pub fn add_command(&mut self, name: &'static str, help: &'static str) -> &mut Self {
+        self.commands.push((name, help));
+        self
+    }
+
+    /// Component types are registered with every new world before any startup system runs.
+    pub fn register_component<ComponentType: Component>(&mut self) -> &mut Self {
+        self.registrations.push(World::register_component::<ComponentType>);
+        self
+    }
+
+    /// Registers a component whose fields can be reached by path.
+    pub fn register_reflect<ComponentType: Component + Reflect>(&mut self) -> &mut Self {
+        self.registrations.push(World::register_reflect::<ComponentType>);
+        self
+    }
+
+    /// Registers a component whose `Debug` value is shown when the world is dumped.
+    pub fn register_debug<ComponentType: Component + fmt::Debug>(&mut self) -> &mut Self {
+        self.registrations.push(World::register_debug::<ComponentType>);
+        self
+    }
+
+    /// Adds a developer command, it can only be used once the console is enabled.
+    pub fn add_dev_command(&mut self, name: &'static str, usage: &'static str, run: DevCommandFn) -> &mut Self {
+        self.console.add(name, usage, run);
+        self
+    }
+
+    /// Lets developer commands be typed in while playing, see [`DevConsole`].
+    pub fn enable_dev_console(&mut self) -> &mut Self {
+        self.console.enabled = true;
+        self
+    }
+
+    /// Swaps how output is shown and input is read, by default it's line by line through [`LineBackend`].
+    pub fn set_backend(&mut self, backend: impl RenderBackend + 'static) -> &mut Self {
+        self.backend = Box::new(backend);
+        self
+    }
+
+    /// The state starting over goes back to, by default the one the game starts in.
+    pub fn set_restart_state(&mut self, state: S) -> &mut Self {
+        self.restart_state = state;
+        self
+    }
+
+    /// Throws away the old world and builds a new one from the startup systems.
+    pub fn reset(&mut self) {
+        let mut world = World::new();
+        world.add_resource(State::new(self.initial_state));
+        world.add_resource(Session::default());
+        world.add_resource(CommandHelp { commands: self.commands.clone() });
+        let mut queue = RenderQueue::default();
+        if self.console.enabled {
+            queue.show(Channel::Debug);
+        }
+        world.add_resource(queue);
+        world.add_resource(TextLayout::default());
+        for system in self.registrations.iter().chain(&self.startup) {
+            system(&mut world);
+        }
+        self.world = world;
+    }
+
+    /// Reads commands from stdin and prints what the systems have to say until the game quits.
+    pub fn run(&mut self) {
+        let mut buffer = String::new();
+        self.reset();
+        self.schedule.start(&self.world);
+        loop {
+            self.backend.render(&self.world);
+            let (quit, restart) = {
+                let session = self.world.borrow_resource::<Session>().unwrap();
+                (session.quit, session.restart)
+            };
+            if quit {
+                break;
+            }
+            if restart {
+                // The new world enters its first state properly before moving on to wherever starting over goes
+                self.reset();
+                self.schedule.start(&self.world);
+                self.world.borrow_resource_mut::<State<S>>().unwrap().set(self.restart_state);
+                self.schedule.apply_transitions(&self.world);
+                continue;
+            }
+            // Running out of input is the same as asking to quit
+            if !self.backend.read_line(&mut buffer) {
+                break;
+            }
+            let command_vec = process_string(&buffer);
+            // Developer commands skip the game's systems entirely
+            if let Some(console_output) = self.console.run(&mut self.world, &command_vec) {
+                self.world.borrow_resource_mut::<RenderQueue>().unwrap().push(Message::system(console_output));
+                continue;
+            }
+            self.schedule.run(&self.world, &command_vec);
+        }
+    }
+}
+
+// False once there's no more input to read
+fn get_input(buffer: &mut String) -> bool {
+    io::Write::flush(&mut io::stdout()).ok();
+    buffer.clear();
+
+    matches!(io::stdin().read_line(buffer), Ok(read) if read > 0)
+}
+
+// Help, pause and quitting are commands like any other, the game states decide what they do
+fn process_string(buffer: &str) -> Vec<&str> {
+    buffer.split_ascii_whitespace().collect()
+}
+
+/// Prints the messages queued up this frame, styled with ANSI colors when stdout is a terminal.
+///
+/// Lines are wrapped to the world's [`TextLayout`] and output longer than a screen waits on `-- more --`
+/// between pages. Setting `NO_COLOR` prints plain text even on a terminal.
+pub fn render_system(world: &World) {
+    let display_text = match world.borrow_resource_mut::<RenderQueue>() {
+        Some(mut queue) => queue.compose(),
+        None => return,
+    };
+    if display_text.is_empty() {
+        return;
+    }
+    let terminal = io::IsTerminal::is_terminal(&io::stdout());
+    let style = markup_style(terminal);
+    let layout = world.borrow_resource::<TextLayout>().map_or_else(TextLayout::default, |layout| *layout);
+    let size = if terminal { terminal_size::terminal_size() } else { None };
+    let display_text = match layout.width.or(size.map(|(width, _)| width.0 as usize)) {
+        Some(width) => wrap_markup(&display_text, width, layout.indent),
+        None => display_text,
+    };
+    // Waiting on the player only makes sense when they're the one typing
+    let height = match layout.height.or(size.map(|(_, height)| height.0 as usize)) {
+        Some(height) if terminal && io::IsTerminal::is_terminal(&io::stdin()) => height.saturating_sub(1).max(1),
+        _ => usize::MAX,
+    };
+    let lines: Vec<&str> = display_text.split('\n').collect();
+    for (index, page) in lines.chunks(height).enumerate() {
+        if index > 0 {
+            print!("{}", render_markup("> -- more --", style));
+            io::Write::flush(&mut io::stdout()).ok();
+            if !wait_for_more(world) {
+                println!();
+                return;
+            }
+        }
+        println!("{}", render_markup(&page.join("\n"), style));
+    }
+}
+
+// Waits for the player to press enter at `-- more --`, running out of input there is the same as asking to quit
+fn wait_for_more(world: &World) -> bool {
+    let read = matches!(io::stdin().read_line(&mut String::new()), Ok(read) if read > 0);
+    if !read {
+        if let Some(mut session) = world.borrow_resource_mut::<Session>() {
+            session.quit = true;
+        }
+    }
+    read
+}
+
+// Colors are only for terminals, and not even then when NO_COLOR is set
+fn markup_style(terminal: bool) -> MarkupStyle {
+    if terminal && std::env::var_os("NO_COLOR").is_none() {
+        MarkupStyle::Ansi
+    } else {
+        MarkupStyle::Plain
+    }
+}
+
+/// Where the app shows each frame's output and reads the player's input from.
+///
+/// Systems only ever push onto the [`RenderQueue`], so they work the same whichever backend is in use.
+pub trait RenderBackend {
+    /// Shows whatever was queued up during the frame.
+    fn render(&mut self, world: &World);
+    /// Reads the player's next line into the buffer, false once there's no more input.
+    fn read_line(&mut self, buffer: &mut String) -> bool;
+}
+
+/// Prints each frame below the last one with [`render_system`] and reads input from stdin.
+pub struct LineBackend;
+
+impl RenderBackend for LineBackend {
+    fn render(&mut self, world: &World) {
+        render_system(world);
+    }
+
+    fn read_line(&mut self, buffer: &mut String) -> bool {
+        get_input(buffer)
+    }
+}
+
+// How many lines the TUI keeps to show, older ones are dropped
+const TUI_HISTORY: usize = 500;
+
+/// A full screen terminal UI: the story scrolls by above a status bar and the line the player types on.
+///
+/// The status bar is whatever markup the game's status function returns for the world, redrawn every frame.
+/// A frame with more to say than fits waits on `-- more --` between screens like [`render_system`] does.
+pub struct TuiBackend {
+    status: fn(&World) -> String,
+    history: Vec<String>,
+    status_line: String,
+    started: bool,
+}
+
+impl TuiBackend {
+    pub fn new(status: fn(&World) -> String) -> Self {
+        TuiBackend { status, history: Vec::new(), status_line: String::new(), started: false }
+    }
+
+    fn size() -> (usize, usize) {
+        terminal_size::terminal_size().map_or((80, 24), |(width, height)| (width.0 as usize, height.0 as usize))
+    }
+
+    // Draws the history up to `bottom` in the pane, with the status bar and prompt underneath
+    fn draw(&self, bottom: usize, prompt: &str) {
+        let (width, height) = Self::size();
+        let pane = height.saturating_sub(2).max(1);
+        let style = markup_style(true);
+        let mut screen = String::from("\x1b[2J");
+        for (row, line) in self.history[bottom.saturating_sub(pane)..bottom].iter().enumerate() {
+            screen.push_str(&format!("\x1b[{};1H{}", row + 1, render_markup(line, style)));
+        }
+        let status: String = render_markup(&self.status_line, MarkupStyle::Plain).chars().take(width).collect();
+        screen.push_str(&format!("\x1b[{};1H\x1b[7m{:<width$}\x1b[0m", height.saturating_sub(1).max(1), status, width = width));
+        screen.push_str(&format!("\x1b[{};1H{}", height, prompt));
+        print!("{}", screen);
+        io::Write::flush(&mut io::stdout()).ok();
+    }
+}
+
+impl RenderBackend for TuiBackend {
+    fn render(&mut self, world: &World) {
+        if !self.started {
+            // The alternate screen leaves the player's terminal as it was once the game is over
+            print!("\x1b[?1049h");
+            self.started = true;
+        }
+        let text = world.borrow_resource_mut::<RenderQueue>().map(|mut queue| queue.compose()).unwrap_or_default();
+        self.status_line = (self.status)(world);
+        let (width, height) = Self::size();
+        let pane = height.saturating_sub(2).max(1);
+        let first_new = self.history.len();
+        if !text.is_empty() {
+            self.history.extend(wrap_markup(&text, width, 2).split('\n').map(String::from));
+        }
+        // Like render_system, only wait on the player when they're the one typing
+        let mut bottom = if io::IsTerminal::is_terminal(&io::stdin()) {
+            (first_new + pane).min(self.history.len())
+        } else {
+            self.history.len()
+        };
+        while bottom < self.history.len() {
+            self.draw(bottom, &render_markup("> -- more --", markup_style(true)));
+            if !wait_for_more(world) {
+                bottom = self.history.len();
+                break;
+            }
+            bottom = (bottom + pane).min(self.history.len());
+        }
+        self.draw(bottom, "> ");
+        let excess = self.history.len().saturating_sub(TUI_HISTORY);
+        self.history.drain(..excess);
+    }
+
+    fn read_line(&mut self, buffer: &mut String) -> bool {
+        let read = get_input(buffer);
+        // What the player typed stays in the story above what it led to
+        if read && !buffer.trim().is_empty() {
+            self.history.push(format!("> {}", buffer.trim()));
+        }
+        read
+    }
+}
+
+impl Drop for TuiBackend {
+    fn drop(&mut self) {
+        if self.started {
+            print!("\x1b[?1049l");
+            io::Write::flush(&mut io::stdout()).ok();
+        }
+    }
+}
+
+/// Plays back lines of input in order and keeps every frame that had something to show, for testing apps.
+#[doc(hidden)]
+pub struct ScriptedBackend {
+    input: VecDeque<String>,
+    shown: Rc<RefCell<Vec<String>>>,
+}
+
+impl ScriptedBackend {
+    pub fn new(input: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        ScriptedBackend { input: input.into_iter().map(Into::into).collect(), shown: Rc::default() }
+    }
+
+    /// The frames shown so far, the app owns the backend once it's set so keep this to look at them after a run.
+    pub fn shown(&self) -> Rc<RefCell<Vec<String>>> {
+        self.shown.clone()
+    }
+}
+
+impl RenderBackend for ScriptedBackend {
+    fn render(&mut self, world: &World) {
+        let text = world.borrow_resource_mut::<RenderQueue>().map(|mut queue| queue.compose()).unwrap_or_default();
+        if !text.is_empty() {
+            self.shown.borrow_mut().push(text);
+        }
+    }
+
+    fn read_line(&mut self, buffer: &mut String) -> bool {
+        buffer.clear();
+        match self.input.pop_front() {
+            Some(line) => {
+                buffer.push_str(&line);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// How output is laid out on the screen, the app adds one to every world and startup systems can replace it.
+#[derive(Clone, Copy, Debug)]
+pub struct TextLayout {
+    /// Columns to wrap at, the terminal's width when `None`. Output that isn't going to a terminal isn't wrapped.
+    pub width: Option<usize>,
+    /// Lines to show before waiting on `-- more --`, the terminal's height when `None`.
+    pub height: Option<usize>,
+    /// How far the lines a long line is wrapped onto are indented.
+    pub indent: usize,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        TextLayout { width: None, height: None, indent: 2 }
+    }
+}
+
+/// What kind of message something is, which decides how it's shown and whether it's shown at all.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Channel {
+    /// What happens in the game, the default for anything a system writes.
+    Narration,
+    /// Nudges towards what the player could do next.
+    Hint,
+    /// Messages about the game itself rather than anything in it.
+    System,
+    /// Details for developers, only shown once the developer console is enabled.
+    Debug,
+}
+
+/// How much a message matters when more than one thing has something to say in a frame.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+    /// Replaces every other message on its channel in the frame, such as the game ending.
+    Critical,
+}
+
+/// A line or more of text for the player along with where it came from.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Message {
+    pub text: String,
+    pub channel: Channel,
+    pub priority: Priority,
+    /// The entity the message is about, messages from the same entity compete with each other.
+    /// Messages without a source never compete, each one is shown.
+    pub source: Option<usize>,
+}
+
+impl Message {
+    pub fn new(channel: Channel, text: impl Into<String>) -> Self {
+        Message { text: text.into(), channel, priority: Priority::Normal, source: None }
+    }
+
+    pub fn narration(text: impl Into<String>) -> Self {
+        Self::new(Channel::Narration, text)
+    }
+
+    pub fn hint(text: impl Into<String>) -> Self {
+        Self::new(Channel::Hint, text)
+    }
+
+    pub fn system(text: impl Into<String>) -> Self {
+        Self::new(Channel::System, text)
+    }
+
+    pub fn debug(text: impl Into<String>) -> Self {
+        Self::new(Channel::Debug, text)
+    }
+
+    pub fn from(mut self, source: usize) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// Every message pushed during a frame, composed into the text that's printed at the end of it.
+///
+/// Composing is the same no matter which system ran last:
+/// - a [`Priority::Critical`] message replaces everything on its channel that isn't critical,
+/// - otherwise messages on the same channel from the same source only keep the highest priority ones,
+/// - the same text on the same channel is only shown once, whoever said it,
+/// - and what's left is shown in the order it was pushed.
+pub struct RenderQueue {
+    messages: Vec<Message>,
+    hidden: Vec<Channel>,
+}
+
+impl Default for RenderQueue {
+    fn default() -> Self {
+        RenderQueue { messages: Vec::new(), hidden: vec![Channel::Debug] }
+    }
+}
+
+impl RenderQueue {
+    pub fn push(&mut self, message: Message) {
+        self.messages.push(message);
+    }
+
+    pub fn show(&mut self, channel: Channel) {
+        self.hidden.retain(|hidden| *hidden != channel);
+    }
+
+    pub fn hide(&mut self, channel: Channel) {
+        if !self.hidden.contains(&channel) {
+            self.hidden.push(channel);
+        }
+    }
+
+    /// Takes the frame's messages and turns them into markup for [`render_markup`].
+    pub fn compose(&mut self) -> String {
+        let messages: Vec<Message> = self.messages.drain(..).filter(|message| !self.hidden.contains(&message.channel)).collect();
+        let critical: Vec<Channel> =
+            messages.iter().filter(|message| message.priority == Priority::Critical).map(|message| message.channel).collect();
+        let mut shown: Vec<&Message> = Vec::new();
+        for message in &messages {
+            let outranked = if critical.contains(&message.channel) {
+                message.priority != Priority::Critical
+            } else {
+                message.source.is_some()
+                    && messages.iter().any(|other| {
+                        other.channel == message.channel && other.source == message.source && other.priority > message.priority
+                    })
+            };
+            // Two things can say the same thing, such as two npcs with the same description
+            let repeated = shown.iter().any(|other| other.channel == message.channel && other.text == message.text);
+            if !outranked && !repeated {
+                shown.push(message);
+            }
+        }
+        let lines: Vec<String> = shown
+            .iter()
+            .map(|message| match (message.channel, message.source) {
+                (Channel::Narration | Channel::Hint, _) => message.text.clone(),
+                (Channel::System, _) | (Channel::Debug, None) => system_message(&message.text),
+                (Channel::Debug, Some(source)) => system_message(&format!("[{}] {}", source, message.text)),
+            })
+            .collect();
+        lines.join("\n")
+    }
+}
+
+/// How [`render_markup`] shows the markup it finds.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MarkupStyle {
+    /// Colors and emphasis through ANSI escape codes, for terminals.
+    Ansi,
+    /// The text alone with the markup taken out.
+    Plain,
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_KEYWORD: &str = "\x1b[1;36m";
+const ANSI_EMPHASIS: &str = "\x1b[3m";
+const ANSI_SYSTEM: &str = "\x1b[33m";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Markup {
+    Plain,
+    Keyword,
+    Emphasis,
+}
+
+// Splits a line into runs of text in the same style, a marker without its closing half is plain text
+// and so is a `*` with a space after it, so arithmetic like 3 * 4 * 5 reads as written
+fn parse_markup(line: &str) -> Vec<(Markup, &str)> {
+    let mut runs = Vec::new();
+    let mut rest = line;
+    while !rest.is_empty() {
+        let (open, close, markup) = if rest.starts_with("{{") {
+            ("{{", "}}", Markup::Keyword)
+        } else if rest.starts_with('*') && rest[1..].starts_with(|c: char| !c.is_whitespace()) {
+            ("*", "*", Markup::Emphasis)
+        } else {
+            // A lone brace or star is plain text, so the search for the next marker starts after it
+            let first = rest.chars().next().map_or(0, char::len_utf8);
+            let next = rest[first..].find(['{', '*']).map_or(rest.len(), |next| next + first);
+            runs.push((Markup::Plain, &rest[..next]));
+            rest = &rest[next..];
+            continue;
+        };
+        match rest[open.len()..].find(close) {
+            Some(end) => {
+                runs.push((markup, &rest[open.len()..open.len() + end]));
+                rest = &rest[open.len() + end + close.len()..];
+            }
+            None => {
+                runs.push((Markup::Plain, open));
+                rest = &rest[open.len()..];
+            }
+        }
+    }
+    runs
+}
+
+/// Turns the game's markup into text for the player.
+///
+/// `{{keyword}}` highlights a command or item the player can use, `*emphasis*` stresses a word as long as
+/// the opening `*` is right against it, and a line starting with `> ` is a system message rather than narration.
+/// A marker without its closing half is left as it is.
+pub fn render_markup(text: &str, style: MarkupStyle) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            rendered.push('\n');
+        }
+        let (line, base) = match line.strip_prefix("> ") {
+            Some(message) => (message, ANSI_SYSTEM),
+            None => (line, ""),
+        };
+        let ansi = style == MarkupStyle::Ansi;
+        if ansi {
+            rendered.push_str(base);
+        }
+        for (markup, run) in parse_markup(line) {
+            let code = match markup {
+                Markup::Keyword => ANSI_KEYWORD,
+                Markup::Emphasis => ANSI_EMPHASIS,
+                Markup::Plain => "",
+            };
+            if ansi && !code.is_empty() {
+                rendered.push_str(code);
+                rendered.push_str(run);
+                rendered.push_str(ANSI_RESET);
+                rendered.push_str(base);
+            } else {
+                rendered.push_str(run);
+            }
+        }
+        if ansi && !base.is_empty() {
+            rendered.push_str(ANSI_RESET);
+        }
+    }
+    rendered
+}
+
+// Writes runs back out as markup, joining neighbours in the same style
+fn write_markup(runs: &[(Markup, String)]) -> String {
+    let mut merged: Vec<(Markup, String)> = Vec::new();
+    for (markup, run) in runs {
+        match merged.last_mut() {
+            Some((last, text)) if last == markup => text.push_str(run),
+            _ => merged.push((*markup, run.clone())),
+        }
+    }
+    merged
+        .iter()
+        .map(|(markup, run)| match markup {
+            Markup::Plain => run.clone(),
+            Markup::Keyword => format!("{{{{{}}}}}", run),
+            Markup::Emphasis => format!("*{}*", run),
+        })
+        .collect()
+}
+
+// How many columns text takes up on a terminal, letters with accents take one and most CJK characters two
+fn text_width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Wraps markup at word boundaries so no line is wider than `width` once it's rendered.
+///
+/// The lines a long line carries on onto keep its `> ` and leading spaces and are indented by `indent`.
+/// A keyword or emphasis broken over two lines is closed and opened again so each line stands on its own,
+/// and a single word wider than `width` is left on a line of its own rather than being split.
+pub fn wrap_markup(text: &str, width: usize, indent: usize) -> String {
+    let mut wrapped = Vec::new();
+    for line in text.split('\n') {
+        let (prefix, body) = match line.strip_prefix("> ") {
+            Some(message) => ("> ", message),
+            None => ("", line),
+        };
+        let runs = parse_markup(body);
+        let visible = text_width(prefix) + runs.iter().map(|(_, run)| text_width(run)).sum::<usize>();
+        if visible <= width {
+            wrapped.push(line.to_string());
+            continue;
+        }
+        let lead = &body[..body.len() - body.trim_start_matches(' ').len()];
+        // Each word is the runs it's made of, styles can change partway through a word like "{{Key}},"
+        let mut words: Vec<Vec<(Markup, String)>> = vec![Vec::new()];
+        for (markup, run) in runs {
+            for (index, piece) in run.split(' ').enumerate() {
+                if index > 0 {
+                    words.push(Vec::new());
+                }
+                if !piece.is_empty() {
+                    words.last_mut().unwrap().push((markup, piece.to_string()));
+                }
+            }
+        }
+        let continuation = format!("{}{}{}", prefix, lead, " ".repeat(indent));
+        let mut start = format!("{}{}", prefix, lead);
+        let mut current: Vec<(Markup, String)> = Vec::new();
+        let mut current_width = text_width(&start);
+        for word in words.into_iter().filter(|word| !word.is_empty()) {
+            let word_width: usize = word.iter().map(|(_, run)| text_width(run)).sum();
+            if !current.is_empty() && current_width + 1 + word_width > width {
+                wrapped.push(format!("{}{}", start, write_markup(&current)));
+                current.clear();
+                start = continuation.clone();
+                current_width = text_width(&start);
+            }
+            if !current.is_empty() {
+                // The space takes the style of the words either side when they share one
+                let (before, after) = (current.last().unwrap().0, word[0].0);
+                current.push((if before == after { before } else { Markup::Plain }, String::from(" ")));
+                current_width += 1;
+            }
+            current_width += word_width;
+            current.extend(word);
+        }
+        wrapped.push(format!("{}{}", start, write_markup(&current)));
+    }
+    wrapped.join("\n")
+}
+
+/// Marks every line of `text` as a system message for [`render_markup`].
+pub fn system_message(text: &str) -> String {
+    text.lines().map(|line| format!("> {}", line)).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+    enum TestState {
+        Start,
+        Middle,
+        End,
+    }
+
+    #[derive(Component, PartialEq, Debug)]
+    struct Health(u32);
+
+    #[derive(Component)]
+    #[component(name = "label", storage = "sparse")]
+    struct Label(&'static str);
+
+    #[derive(Component)]
+    struct Unused;
+
+    #[derive(Bundle)]
+    struct Labelled {
+        health: Health,
+        label: Label,
+    }
+
+    #[test]
+    fn components_line_up_with_entities() {
+        let mut world = World::new();
+        world.register_component::<Health>();
+        let first = world.new_entity();
+        let second = world.new_entity();
+        world.add_component_to_entity(second, Health(5));
+        // Entities made before a component is registered still get a slot for it
+        world.register_component::<Label>();
+        world.register_component::<Unused>();
+        let third = world.spawn(Labelled { health: Health(7), label: Label("third") });
+        world.add_component_to_entity(first, Label("first"));
+        assert_eq!(world.entity_count(), 3);
+        assert_eq!(world.query::<Health>(), vec![second, third]);
+        assert_eq!(world.query::<Label>(), vec![first, third]);
+        assert_eq!(world.borrow_component::<Label>().unwrap().get(third).map(|label| label.0), Some("third"));
+        assert!(world.query::<Unused>().is_empty());
+        world.borrow_component_mut::<Health>().unwrap()[third] = None;
+        assert_eq!(world.query::<Health>(), vec![second]);
+
+        let info: Vec<(&str, StorageKind)> = world.component_info().iter().map(|info| (info.name, info.storage)).collect();
+        assert_eq!(info, vec![("Health", StorageKind::Dense), ("label", StorageKind::Sparse), ("Unused", StorageKind::Dense)]);
+        // Sparse components only take up room for the entities that have them
+        let labels = world.borrow_component::<Label>().unwrap();
+        assert_eq!(labels.iter().map(|(entity, label)| (entity, label.0)).collect::<Vec<_>>(), vec![(first, "first"), (third, "third")]);
+        assert_eq!((labels.len(), world.borrow_component::<Health>().unwrap().len()), (2, 3));
+        drop(labels);
+
+        world.add_resource(1u8);
+        world.add_resource(2u8);
+        assert_eq!(*world.borrow_resource::<u8>().unwrap(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "has to be registered")]
+    fn unregistered_components_are_refused() {
+        let mut world = World::new();
+        let entity = world.new_entity();
+        world.add_component_to_entity(entity, Health(1));
+    }
+
+    #[derive(Reflect)]
+    struct Position {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(Component, Reflect)]
+    #[component(name = "mover")]
+    struct Mover {
+        position: Position,
+        speed: f64,
+        is_stuck: bool,
+        #[reflect(skip)]
+        #[allow(unused)]
+        path: Vec<Position>,
+    }
+
+    #[test]
+    fn fields_are_reached_by_path() {
+        let mut world = World::new();
+        world.register_reflect::<Mover>();
+        world.register_component::<Health>();
+        let still = world.new_entity();
+        let mover = world.new_entity();
+        world.add_component_to_entity(mover, Mover { position: Position { x: 1, y: 2 }, speed: 0.5, is_stuck: false, path: Vec::new() });
+
+        let names: Vec<&str> = world.fields_of(mover, "mover").unwrap().iter().map(|field| field.name).collect();
+        assert_eq!(names, vec!["position", "speed", "is_stuck"]);
+        assert_eq!(world.get_path(mover, "mover.position.y"), Ok(Value::Int(2)));
+        world.set_path(mover, "mover.position.x", Value::parse("-4")).unwrap();
+        world.set_path(mover, "mover.speed", Value::parse("3")).unwrap();
+        world.set_path(mover, "mover.is_stuck", Value::parse("true")).unwrap();
+        let components = world.borrow_component::<Mover>().unwrap();
+        let moved = components[mover].as_ref().unwrap();
+        assert_eq!((moved.position.x, moved.speed, moved.is_stuck), (-4, 3.0, true));
+        drop(components);
+
+        assert_eq!(world.get_path(mover, "mover.path"), Err(ReflectError::NoField(String::from("path"))));
+        assert_eq!(world.get_path(mover, "mover.position"), Err(ReflectError::NotAValue(vec!["x", "y"])));
+        assert_eq!(
+            world.set_path(mover, "mover.is_stuck", Value::Int(1)),
+            Err(ReflectError::WrongType { expected: "bool", found: Value::Int(1) })
+        );
+        assert_eq!(world.get_path(still, "mover.speed"), Err(ReflectError::NotOnEntity { entity: still, component: "mover" }));
+        // Only components registered for reflection can be reached
+        assert_eq!(world.get_path(mover, "Health.0"), Err(ReflectError::NoComponent(String::from("Health"))));
+    }
+
+    #[test]
+    fn integers_that_dont_fit_are_errors() {
+        assert_eq!(u64::MAX.get_path(""), Err(ReflectError::OutOfRange(u64::MAX.to_string())));
+        assert_eq!((i64::MAX as u64).get_path(""), Ok(Value::Int(i64::MAX)));
+        let mut small = 1u32;
+        assert_eq!(small.set_path("", Value::Int(-1)), Err(ReflectError::WrongType { expected: "u32", found: Value::Int(-1) }));
+        assert_eq!(small, 1);
+
+        // A world that never registered the component can't have it set
+        let set = ReflectFns::of::<Mover>().set;
+        assert_eq!(set(&World::new(), 0, "speed", Value::Float(1.0)), Err(ReflectError::NoComponent(String::from("mover"))));
+    }
+
+    #[derive(Component, Debug)]
+    struct Named(#[allow(unused)] &'static str);
+
+    #[test]
+    fn dumps_list_entities_and_storages() {
+        let mut world = World::new();
+        world.register_debug::<Named>();
+        world.register_reflect::<Mover>();
+        world.register_component::<Unused>();
+        world.register_component::<Label>();
+        let named = world.new_entity();
+        world.add_component_to_entity(named, Named("the \"first\""));
+        world.add_component_to_entity(named, Label("first"));
+        let mover = world.new_entity();
+        world.add_component_to_entity(mover, Mover { position: Position { x: 1, y: 2 }, speed: 0.5, is_stuck: false, path: Vec::new() });
+        world.add_component_to_entity(mover, Named("second"));
+
+        let text = world.dump().to_text();
+        assert!(text.contains("Entity 1\n  Named"));
+        assert!(text.contains("  mover ("));
+        assert!(text.contains("{ position: { x: 1, y: 2 }, speed: 0.5, is_stuck: false }"));
+        assert!(text.contains("Named Dense 2/2 filled (100%)"));
+        assert!(text.contains("Unused Dense 0/2 filled (0%), "));
+        assert!(text.contains(&format!("label Sparse 1/2 filled (50%), {} bytes", std::mem::size_of::<(usize, Label)>())));
+
+        // A storage in use still shows up, just without what's in it
+        let movers = world.borrow_component_mut::<Mover>().unwrap();
+        let dump = world.dump();
+        let stats = dump.storages.iter().find(|stats| stats.name == "mover").unwrap();
+        assert_eq!((stats.filled, stats.borrow), (None, BorrowState::Mutable));
+        assert!(dump.entities[1].components.iter().all(|component| component.name != "mover"));
+        let json = dump.to_json();
+        drop(movers);
+        assert!(json.starts_with("{\"entities\":[{\"entity\":0,\"components\":[{\"name\":\"Named\",\"type\":"));
+        assert!(json.contains("\"value\":\"Named(\\\"the \\\\\\\"first\\\\\\\"\\\")\"}"));
+        assert!(json.contains("\"storage\":\"dense\",\"filled\":null,\"len\":2,\"fill_ratio\":null,\"bytes\":null,\"borrow\":\"mutable\""));
+    }
+
+    fn go_to_middle(world: &World, input: &[&str], _: &mut String) {
+        if input.first() == Some(&"next") {
+            world.borrow_resource_mut::<State<TestState>>().unwrap().set(TestState::Middle);
+        }
+    }
+
+    // Entering the middle goes straight on to the end
+    fn enter_middle(world: &World, _: &[&str], output: &mut String) {
+        output.push_str("middle");
+        world.borrow_resource_mut::<State<TestState>>().unwrap().set(TestState::End);
+    }
+
+    #[test]
+    fn transitions_settle_before_the_next_frame() {
+        let mut schedule = Schedule::new();
+        schedule
+            .on_update(TestState::Start, Stage::Update, go_to_middle)
+            .on_enter(TestState::Middle, enter_middle)
+            .on_exit(TestState::Middle, |_, _, output| output.push_str("leaving"));
+        let mut world = World::new();
+        world.add_resource(State::new(TestState::Start));
+        world.add_resource(RenderQueue::default());
+        schedule.run(&world, &["wait"]);
+        assert_eq!(world.borrow_resource::<State<TestState>>().unwrap().get(), TestState::Start);
+        schedule.run(&world, &["next"]);
+        assert_eq!(world.borrow_resource::<State<TestState>>().unwrap().get(), TestState::End);
+        assert_eq!(world.borrow_resource_mut::<RenderQueue>().unwrap().compose(), "middle\nleaving");
+    }
+
+    // Runs the app on the input and returns what it showed
+    fn run_scripted(app: &mut App<TestState>, input: &[&'static str]) -> Vec<String> {
+        let backend = ScriptedBackend::new(input.iter().copied());
+        let shown = backend.shown();
+        app.set_backend(backend);
+        app.run();
+        let shown = shown.borrow().clone();
+        shown
+    }
+
+    fn echo(_: &World, input: &[&str], output: &mut String) {
+        output.push_str(&input.join("+"));
+    }
+
+    #[test]
+    fn apps_run_on_any_backend() {
+        let mut app = App::new(TestState::Start);
+        app.add_enter_system(TestState::Start, |_, _, output| output.push_str("ready"))
+            .add_system(TestState::Start, echo);
+        assert_eq!(run_scripted(&mut app, &["go", "to the end"]), vec!["ready", "go", "to+the+end"]);
+    }
+
+    struct Counter(u32);
+
+    struct CounterPlugin;
+
+    impl Plugin<TestState> for CounterPlugin {
+        fn build(&self, app: &mut App<TestState>) {
+            app.register_component::<Health>()
+                .add_startup_system(|world| world.add_resource(Counter(0)))
+                .add_system(TestState::Start, |world, _, output| {
+                    let mut counter = world.borrow_resource_mut::<Counter>().unwrap();
+                    counter.0 += 1;
+                    output.push_str(&counter.0.to_string());
+                })
+                .add_command("Count", "I can {{Count}}");
+        }
+    }
+
+    #[test]
+    fn plugins_fill_in_every_new_world() {
+        let mut app = App::new(TestState::Start);
+        app.add_plugin(CounterPlugin);
+        assert_eq!(run_scripted(&mut app, &["a", "b"]), vec!["1", "2"]);
+        assert_eq!(app.world.borrow_resource::<Counter>().unwrap().0, 2);
+        assert!(app.world.component_info().iter().any(|info| info.name == "Health"));
+        assert_eq!(app.world.borrow_resource::<CommandHelp>().unwrap().render(), "> Available Commands {{Count}}\nI can {{Count}}");
+
+        // Resetting builds the world again from the startup systems
+        app.reset();
+        assert_eq!(app.world.borrow_resource::<Counter>().unwrap().0, 0);
+    }
+
+    #[test]
+    fn quitting_stops_reading_input() {
+        let mut app = App::new(TestState::Start);
+        app.add_system(TestState::Start, echo).add_system(TestState::Start, |world, input, _| {
+            if input == ["stop"] {
+                world.borrow_resource_mut::<Session>().unwrap().quit = true;
+            }
+        });
+        assert_eq!(run_scripted(&mut app, &["one", "stop", "never"]), vec!["one", "stop"]);
+    }
+
+    fn restart_app() -> App<TestState> {
+        let mut app = App::new(TestState::Start);
+        app.add_startup_system(|world| world.add_resource(Counter(0)))
+            .add_enter_system(TestState::Start, |_, _, output| output.push_str("hello"))
+            .add_exit_system(TestState::Start, |_, _, output| output.push_str("bye"))
+            .add_enter_system(TestState::Middle, |_, _, output| output.push_str("middle"))
+            .add_system(TestState::Start, |world, input, _| {
+                world.borrow_resource_mut::<Counter>().unwrap().0 += 1;
+                if input == ["again"] {
+                    world.borrow_resource_mut::<Session>().unwrap().restart = true;
+                }
+            });
+        app
+    }
+
+    #[test]
+    fn restarting_enters_the_first_state_again() {
+        let mut app = restart_app();
+        assert_eq!(run_scripted(&mut app, &["wait", "again"]), vec!["hello", "hello"]);
+        assert_eq!(app.world.borrow_resource::<State<TestState>>().unwrap().get(), TestState::Start);
+        assert_eq!(app.world.borrow_resource::<Counter>().unwrap().0, 0);
+
+        // Starting over somewhere else leaves the first state the same way it would have been left in play
+        let mut app = restart_app();
+        app.set_restart_state(TestState::Middle);
+        assert_eq!(run_scripted(&mut app, &["again"]), vec!["hello", "hello\nbye\nmiddle"]);
+        assert_eq!(app.world.borrow_resource::<State<TestState>>().unwrap().get(), TestState::Middle);
+    }
+
+    #[test]
+    fn wrapping_keeps_markup_whole() {
+        let text = "I could {{Check Pocket}} or look *all around* me\n> Short";
+        assert_eq!(wrap_markup(text, 80, 2), text);
+        assert_eq!(
+            wrap_markup(text, 16, 2),
+            "I could {{Check}}\n  {{Pocket}} or look\n  *all around* me\n> Short"
+        );
+        assert_eq!(
+            wrap_markup("> Available Commands {{Pause, Quit, Move}}", 24, 2),
+            "> Available Commands\n>   {{Pause, Quit, Move}}"
+        );
+        // Rendered, the wrapped lines are no wider than asked for
+        let rendered = render_markup(&wrap_markup(text, 16, 2), MarkupStyle::Plain);
+        assert!(rendered.lines().all(|line| text_width(line) <= 16));
+    }
+
+    #[test]
+    fn wrapping_measures_what_the_terminal_shows() {
+        // Each of these takes several bytes but only one column
+        let text = "I can’t see the café from here, it’s éasy to get lost";
+        assert_eq!(wrap_markup(text, 20, 2), "I can’t see the café\n  from here, it’s\n  éasy to get lost");
+        // While these take two columns each
+        assert_eq!(wrap_markup("{{雪}} 雪雪 雪雪雪", 7, 0), "{{雪}} 雪雪\n雪雪雪");
+        let rendered = render_markup(&wrap_markup(text, 20, 2), MarkupStyle::Plain);
+        assert!(rendered.lines().all(|line| text_width(line) <= 20));
+    }
+
+    #[test]
+    fn render_queue_composes_by_priority() {
+        let mut queue = RenderQueue::default();
+        queue.push(Message::narration("The dog comes up to me"));
+        queue.push(Message::narration("I have no use for this rock right now").from(1));
+        queue.push(Message::narration("I smash the window").from(1).with_priority(Priority::High));
+        queue.push(Message::debug("door opened").from(2));
+        queue.push(Message::hint("I can {{Go}} through now"));
+        queue.push(Message::system("Paused"));
+        assert_eq!(queue.compose(), "The dog comes up to me\nI smash the window\nI can {{Go}} through now\n> Paused");
+
+        queue.show(Channel::Debug);
+        queue.push(Message::debug("door opened").from(2));
+        queue.push(Message::narration("I warm up a little"));
+        queue.push(Message::narration("I freeze").with_priority(Priority::Critical));
+        assert_eq!(queue.compose(), "> [2] door opened\nI freeze");
+        queue.push(Message::debug("door opened").from(2));
+        assert_eq!(queue.compose(), "> [2] door opened");
+    }
+
+    #[test]
+    fn critical_messages_only_replace_their_own_channel() {
+        let mut queue = RenderQueue::default();
+        queue.push(Message::narration("I warm up a little"));
+        queue.push(Message::system("Saved"));
+        queue.push(Message::narration("I freeze").with_priority(Priority::Critical));
+        queue.push(Message::hint("I could {{Restart}}"));
+        assert_eq!(queue.compose(), "> Saved\nI freeze\nI could {{Restart}}");
+    }
+
+    #[test]
+    fn only_messages_with_a_source_compete_on_priority() {
+        let mut queue = RenderQueue::default();
+        queue.push(Message::narration("The wind howls").with_priority(Priority::Low));
+        queue.push(Message::narration("I smash the window").with_priority(Priority::High));
+        queue.push(Message::narration("I have no use for this rock right now").from(1));
+        queue.push(Message::narration("The dog barks").from(2).with_priority(Priority::Low));
+        assert_eq!(queue.compose(), "The wind howls\nI smash the window\nI have no use for this rock right now\nThe dog barks");
+    }
+
+    #[test]
+    fn the_same_text_on_a_channel_is_shown_once() {
+        let mut queue = RenderQueue::default();
+        queue.push(Message::narration("A dog sniffs the snow").from(1));
+        queue.push(Message::narration("A dog sniffs the snow").from(2));
+        queue.push(Message::narration("A dog sniffs the snow").from(2));
+        queue.push(Message::hint("I could {{Look}} around"));
+        queue.push(Message::hint("I could {{Look}} around"));
+        queue.push(Message::system("I could {{Look}} around"));
+        assert_eq!(queue.compose(), "A dog sniffs the snow\nI could {{Look}} around\n> I could {{Look}} around");
+    }
+
+    #[test]
+    fn messages_without_a_source_are_deduped_too() {
+        let mut queue = RenderQueue::default();
+        queue.push(Message::narration("The wind picks up"));
+        queue.push(Message::narration("The wind picks up"));
+        queue.push(Message::narration("The wind picks up").from(3));
+        queue.push(Message::narration("It's getting dark"));
+        assert_eq!(queue.compose(), "The wind picks up\nIt's getting dark");
+    }
+
+    #[test]
+    fn a_star_with_a_space_after_it_is_not_emphasis() {
+        assert_eq!(parse_markup("3 * 4 * 5"), vec![(Markup::Plain, "3 "), (Markup::Plain, "* 4 "), (Markup::Plain, "* 5")]);
+        assert_eq!(render_markup("3 * 4 * 5", MarkupStyle::Ansi), "3 * 4 * 5");
+        assert_eq!(render_markup("a lone * star", MarkupStyle::Plain), "a lone * star");
+        assert_eq!(render_markup("2 * *three* *", MarkupStyle::Ansi), "2 * \x1b[3mthree\x1b[0m *");
+    }
+
+    #[test]
+    fn markup_renders_plain_or_with_ansi_codes() {
+        let text = "I should *really* {{Open}} the door\n> The game is paused, I can {{Resume}}";
+        assert_eq!(
+            render_markup(text, MarkupStyle::Plain),
+            "I should really Open the door\nThe game is paused, I can Resume"
+        );
+        assert_eq!(
+            render_markup(text, MarkupStyle::Ansi),
+            "I should \x1b[3mreally\x1b[0m \x1b[1;36mOpen\x1b[0m the door\n\
+             \x1b[33mThe game is paused, I can \x1b[1;36mResume\x1b[0m\x1b[33m\x1b[0m"
+        );
+        // Markers that aren't closed are left alone
+        assert_eq!(render_markup("{ x: 1 } 2 * 3 ≥ {{", MarkupStyle::Plain), "{ x: 1 } 2 * 3 ≥ {{");
+    }
+}